@@ -0,0 +1,441 @@
+//! Keeps the solve results of VK players and ranks them per daily puzzle.
+//!
+//! leaderboard-server, configured by the environment:
+//!   SUDOKU_DB    SQLite file, leaderboard.db by default
+//!   SUDOKU_ADDR  address to listen on, 0.0.0.0:8080 by default
+//!
+//! POST /results   a JSON result as the game's "won" event has it plus "user_id",
+//!                 answers with the result id, the player's rank of the day and
+//!                 the flags of the result
+//! GET  /top       ?difficult=medium[&variant=classic][&chess=off][&size=9]
+//!                 [&day=YYYY-MM-DD][&limit=10][&user_id=N]
+//! GET  /friends   the same as /top with &ids=1,2,3
+//! GET  /health
+//!
+//! Only the puzzles of the day are ranked: every difficulty, variant, chess
+//! rule and size has one a day, built from `generator::daily_seed`, so all
+//! players race on the same grid. A result is taken for today's puzzle or,
+//! when the game was started before midnight UTC, for yesterday's. Every
+//! player is ranked by their fastest solve, the rankings answer
+//! {"difficult","variant","chess","size","day","entries":[{"rank","user_id",
+//! "elapsed","score"}]} and "me" with the rank of `user_id` when it is given.
+//!
+//! Every result is checked by `game::verify`: the puzzle of the day is built
+//! again from the seed, once for all its results, and the move log is played
+//! on it. Impossible solves are answered with 422 and not stored, suspicious
+//! ones are stored with their flags and left out of the rankings until
+//! someone looks at them.
+
+use std::collections::HashMap;
+use std::env;
+use std::io::Read;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rusqlite::{params, Connection};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use sudoku_vk::game::generator::{self, Chess, Difficult, Generated, Variant};
+use sudoku_vk::game::replay::Move;
+use sudoku_vk::game::verify::{self, Claim};
+use sudoku_vk::json;
+
+// the move log of a 16x16 solve is tens of KB
+const MAX_BODY: u64 = 1 << 20;
+const MAX_LIMIT: i64 = 100;
+const MAX_FRIENDS: usize = 1000;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS results (
+        id INTEGER PRIMARY KEY,
+        user_id INTEGER NOT NULL,
+        difficult TEXT NOT NULL,
+        day TEXT NOT NULL,
+        variant TEXT NOT NULL,
+        chess TEXT NOT NULL,
+        size INTEGER NOT NULL,
+        seed TEXT NOT NULL,
+        elapsed REAL NOT NULL,
+        score INTEGER NOT NULL,
+        mistakes INTEGER NOT NULL,
+        hints INTEGER NOT NULL,
+        moves TEXT NOT NULL,
+        -- comma separated names from game::verify, empty for a clean solve
+        flags TEXT NOT NULL,
+        created_at INTEGER NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS results_by_puzzle ON results (day, difficult, variant, chess, size, elapsed);
+";
+
+/// The settings of a daily puzzle, every one is ranked on its own.
+struct Puzzle {
+    difficult: Difficult,
+    variant: Variant,
+    chess: Chess,
+    size: usize,
+}
+
+/// One solve as the game sends it.
+struct Submission {
+    user_id: i64,
+    puzzle: Puzzle,
+    seed: u64,
+    // UTC days since 1970-01-01 of the daily puzzle
+    day: i64,
+    elapsed: f64,
+    score: i64,
+    mistakes: i64,
+    hints: i64,
+    moves: Vec<Move>,
+    // the log as it came, kept to look at flagged results
+    moves_json: String,
+}
+
+// Daily puzzles built for checking results by day and settings, only today's
+// and yesterday's are kept. Generating a hard 9x9 takes a while, every
+// result of the same puzzle is checked against the one built first.
+type Dailies = HashMap<(i64, Difficult, Variant, Chess, usize), Generated>;
+
+struct Ranked {
+    rank: i64,
+    user_id: i64,
+    elapsed: f64,
+    score: i64,
+}
+
+fn now() -> i64 {
+    return SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or(0);
+}
+
+fn today() -> i64 {
+    return now().div_euclid(86400);
+}
+
+/// YYYY-MM-DD of the UTC day `time`, in seconds since 1970-01-01, falls on.
+fn utc_day(time: i64) -> String {
+    // Howard Hinnant's civil_from_days
+    let z = time.div_euclid(86400) + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    return format!("{:04}-{:02}-{:02}", year, month, day);
+}
+
+fn is_day(text: &str) -> bool {
+    let bytes = text.as_bytes();
+    return bytes.len() == 10
+        && bytes.iter().enumerate().all(|(i, b)| if i == 4 || i == 7 { *b == b'-' } else { b.is_ascii_digit() });
+}
+
+fn parse_submission(body: &str) -> Result<Submission, String> {
+    let json = json::parse(body).ok_or("body is not JSON")?;
+    let number = |key: &str| json.get(key).and_then(|v| v.as_f64()).ok_or(format!("no number \"{}\"", key));
+    let text = |key: &str| json.get(key).and_then(|v| v.as_str()).ok_or(format!("no string \"{}\"", key));
+
+    let user_id = number("user_id")? as i64;
+    if user_id <= 0 {
+        return Err("user_id must be positive".to_string());
+    }
+    let difficult = Difficult::by_name(text("difficult")?).ok_or("unknown difficult")?;
+    let variant = Variant::by_name(text("variant")?).ok_or("unknown variant")?;
+    let chess = Chess::by_name(text("chess")?).ok_or("unknown chess")?;
+    let size = number("size")? as usize;
+    // the generator can't build a 4x4 without kings touching, the game never asks for one
+    if !generator::is_supported(variant, chess, size) {
+        return Err("only classic puzzles without chess rules come in sizes other than 9".to_string());
+    }
+    // the editor's and the page's puzzles have no seed and aren't ranked
+    let seed = text("seed")?.parse().map_err(|_| "seed is not a number")?;
+    let day = number("day").map_err(|_| "only the puzzle of the day is ranked")? as i64;
+    let elapsed = number("elapsed")?;
+    if !elapsed.is_finite() || elapsed <= 0. {
+        return Err("elapsed must be positive".to_string());
+    }
+    let log = json.get("moves").and_then(|m| m.as_array()).ok_or("no array \"moves\"")?;
+    let mut moves = vec![];
+    for (i, m) in log.iter().enumerate() {
+        moves.push(Move::from_json(m).ok_or(format!("move {} is not a move", i + 1))?);
+    }
+    let moves_json = format!("[{}]", moves.iter().map(|m| m.to_json()).collect::<Vec<_>>().join(","));
+    return Ok(Submission {
+        user_id,
+        puzzle: Puzzle { difficult, variant, chess, size },
+        seed,
+        day,
+        elapsed,
+        score: number("score")? as i64,
+        mistakes: number("mistakes")? as i64,
+        hints: number("hints")? as i64,
+        moves,
+        moves_json,
+    });
+}
+
+/// Best time of every player on the puzzle of the day, fastest first.
+/// `friends` limits the players to those ids.
+fn rankings(db: &Connection, puzzle: &Puzzle, day: &str, friends: Option<&[i64]>) -> rusqlite::Result<Vec<Ranked>> {
+    let filter = match friends {
+        None => String::new(),
+        // the ids are parsed numbers, nothing from the request gets here as text
+        Some(ids) => format!(" AND user_id IN ({})", ids.iter().map(|id| id.to_string()).collect::<Vec<_>>().join(",")),
+    };
+    // SQLite takes `score` from the row with the smallest elapsed
+    let sql = format!(
+        "SELECT user_id, MIN(elapsed) AS best, score FROM results
+         WHERE day = ?1 AND difficult = ?2 AND variant = ?3 AND chess = ?4 AND size = ?5 AND flags = ''{}
+         GROUP BY user_id ORDER BY best, user_id",
+        filter
+    );
+    let mut statement = db.prepare(&sql)?;
+    let values = params![day, puzzle.difficult.name(), puzzle.variant.name(), puzzle.chess.name(), puzzle.size as i64];
+    let rows = statement.query_map(values, |row| {
+        Ok(Ranked { rank: 0, user_id: row.get(0)?, elapsed: row.get(1)?, score: row.get(2)? })
+    })?;
+    let mut ranked = vec![];
+    for (i, row) in rows.enumerate() {
+        let mut row = row?;
+        row.rank = i as i64 + 1;
+        ranked.push(row);
+    }
+    return Ok(ranked);
+}
+
+fn ranked_json(ranked: &Ranked) -> String {
+    return format!(
+        "{{\"rank\":{},\"user_id\":{},\"elapsed\":{:.1},\"score\":{}}}",
+        ranked.rank, ranked.user_id, ranked.elapsed, ranked.score
+    );
+}
+
+fn rankings_json(puzzle: &Puzzle, day: &str, ranked: &[Ranked], limit: usize, user_id: Option<i64>) -> String {
+    let entries: Vec<String> = ranked.iter().take(limit).map(ranked_json).collect();
+    let mut fields = vec![
+        format!("\"difficult\":\"{}\"", puzzle.difficult.name()),
+        format!("\"variant\":\"{}\"", puzzle.variant.name()),
+        format!("\"chess\":\"{}\"", puzzle.chess.name()),
+        format!("\"size\":{}", puzzle.size),
+        format!("\"day\":{}", json::quote(day)),
+        format!("\"entries\":[{}]", entries.join(",")),
+    ];
+    if let Some(user_id) = user_id {
+        match ranked.iter().find(|r| r.user_id == user_id) {
+            Some(me) => fields.push(format!("\"me\":{}", ranked_json(me))),
+            None => fields.push("\"me\":null".to_string()),
+        }
+    }
+    return format!("{{{}}}", fields.join(","));
+}
+
+/// The query string of `url` as pairs, without percent decoding: none of
+/// the parameters has characters that need it.
+fn query(url: &str) -> Vec<(String, String)> {
+    let query = match url.split_once('?') {
+        None => { return vec![]; }
+        Some((_, query)) => query,
+    };
+    return query
+        .split('&')
+        .filter(|p| !p.is_empty())
+        .map(|p| match p.split_once('=') {
+            None => (p.to_string(), String::new()),
+            Some((k, v)) => (k.to_string(), v.to_string()),
+        })
+        .collect();
+}
+
+fn param<'a>(params: &'a [(String, String)], key: &str) -> Option<&'a str> {
+    return params.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str());
+}
+
+fn error(text: &str) -> String {
+    return format!("{{\"error\":{}}}", json::quote(text));
+}
+
+fn submit(db: &Connection, dailies: &mut Dailies, body: &str) -> (u16, String) {
+    let result = match parse_submission(body) {
+        Err(e) => { return (400, error(&e)); }
+        Ok(result) => result,
+    };
+    let puzzle = &result.puzzle;
+    // started before midnight, finished after it
+    if result.day != today() && result.day != today() - 1 {
+        return (422, error("not the puzzle of today"));
+    }
+    if result.seed != generator::daily_seed(result.day, puzzle.variant, puzzle.chess, puzzle.size, puzzle.difficult) {
+        return (422, error("not the puzzle of the day"));
+    }
+    let claim = Claim {
+        variant: puzzle.variant,
+        chess: puzzle.chess,
+        size: puzzle.size,
+        difficult: puzzle.difficult,
+        seed: result.seed,
+        elapsed: result.elapsed,
+        hints: result.hints as u32,
+        moves: &result.moves,
+    };
+    let key = (result.day, puzzle.difficult, puzzle.variant, puzzle.chess, puzzle.size);
+    if !dailies.contains_key(&key) {
+        let generated = match generator::generate(puzzle.variant, puzzle.chess, puzzle.size, puzzle.difficult, result.seed, true) {
+            None => { return (422, error("the settings can't make a puzzle")); }
+            Some(generated) => generated,
+        };
+        let yesterday = today() - 1;
+        dailies.retain(|(day, ..), _| *day >= yesterday);
+        dailies.insert(key, generated);
+    }
+    let flags = match verify::verify_with(&dailies[&key], &claim) {
+        Err(e) => { return (422, error(&e)); }
+        Ok(flags) => flags,
+    };
+    let day = utc_day(result.day * 86400);
+    let inserted = db.execute(
+        "INSERT INTO results (user_id, difficult, day, variant, chess, size, seed, elapsed, score, mistakes, hints, moves, flags, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+        params![
+            result.user_id,
+            puzzle.difficult.name(),
+            day,
+            puzzle.variant.name(),
+            puzzle.chess.name(),
+            puzzle.size as i64,
+            result.seed.to_string(),
+            result.elapsed,
+            result.score,
+            result.mistakes,
+            result.hints,
+            result.moves_json,
+            flags.join(","),
+            now(),
+        ],
+    );
+    if let Err(e) = inserted {
+        return (500, error(&e.to_string()));
+    }
+    let id = db.last_insert_rowid();
+    let rank = match rankings(db, puzzle, &day, None) {
+        Err(e) => { return (500, error(&e.to_string())); }
+        // 0 while all results of the player today are flagged
+        Ok(ranked) => ranked.iter().find(|r| r.user_id == result.user_id).map(|r| r.rank).unwrap_or(0),
+    };
+    let flags: Vec<String> = flags.iter().map(|f| json::quote(f)).collect();
+    return (
+        200,
+        format!("{{\"id\":{},\"day\":{},\"rank\":{},\"flags\":[{}]}}", id, json::quote(&day), rank, flags.join(",")),
+    );
+}
+
+fn top(db: &Connection, params: &[(String, String)], is_friends: bool) -> (u16, String) {
+    let difficult = match param(params, "difficult").and_then(Difficult::by_name) {
+        None => { return (400, error("unknown difficult")); }
+        Some(difficult) => difficult,
+    };
+    let variant = match param(params, "variant").map(Variant::by_name) {
+        None => Variant::Classic,
+        Some(Some(variant)) => variant,
+        Some(None) => { return (400, error("unknown variant")); }
+    };
+    let chess = match param(params, "chess").map(Chess::by_name) {
+        None => Chess::Off,
+        Some(Some(chess)) => chess,
+        Some(None) => { return (400, error("unknown chess")); }
+    };
+    let size = match param(params, "size").map(|s| s.parse::<usize>()) {
+        None => 9,
+        Some(Ok(size)) => size,
+        Some(Err(_)) => { return (400, error("size is not a number")); }
+    };
+    if !generator::is_supported(variant, chess, size) {
+        return (400, error("only classic puzzles without chess rules come in sizes other than 9"));
+    }
+    let puzzle = Puzzle { difficult, variant, chess, size };
+    let today = utc_day(now());
+    let day = param(params, "day").unwrap_or(&today);
+    if !is_day(day) {
+        return (400, error("day must be YYYY-MM-DD"));
+    }
+    let limit = match param(params, "limit").map(|l| l.parse::<i64>()) {
+        None => 10,
+        Some(Ok(limit)) if limit > 0 => limit.min(MAX_LIMIT),
+        Some(_) => { return (400, error("limit must be a positive number")); }
+    };
+    let user_id = match param(params, "user_id").map(|id| id.parse::<i64>()) {
+        None => None,
+        Some(Ok(id)) => Some(id),
+        Some(Err(_)) => { return (400, error("user_id is not a number")); }
+    };
+    let mut friends = vec![];
+    if is_friends {
+        for id in param(params, "ids").unwrap_or("").split(',').filter(|id| !id.is_empty()) {
+            match id.parse::<i64>() {
+                Ok(id) => friends.push(id),
+                Err(_) => { return (400, error("ids must be numbers")); }
+            }
+        }
+        if friends.is_empty() || friends.len() > MAX_FRIENDS {
+            return (400, error("ids must have 1 to 1000 numbers"));
+        }
+        // the player is compared with the friends as well
+        friends.extend(user_id);
+    }
+    let ranked = rankings(db, &puzzle, day, if is_friends { Some(&friends) } else { None });
+    match ranked {
+        Err(e) => (500, error(&e.to_string())),
+        Ok(ranked) => {
+            // friends are few, they all fit
+            let limit = if is_friends { ranked.len() } else { limit as usize };
+            (200, rankings_json(&puzzle, day, &ranked, limit, user_id))
+        }
+    }
+}
+
+fn handle(db: &Connection, dailies: &mut Dailies, request: &mut Request) -> (u16, String) {
+    let path = request.url().split('?').next().unwrap_or("").to_string();
+    match (request.method(), path.as_str()) {
+        (Method::Options, _) => (204, String::new()),
+        (Method::Get, "/health") => (200, "{\"ok\":true}".to_string()),
+        (Method::Post, "/results") => {
+            let mut body = String::new();
+            if request.as_reader().take(MAX_BODY).read_to_string(&mut body).is_err() {
+                return (400, error("body is not UTF-8"));
+            }
+            submit(db, dailies, &body)
+        }
+        (Method::Get, "/top") => top(db, &query(request.url()), false),
+        (Method::Get, "/friends") => top(db, &query(request.url()), true),
+        _ => (404, error("not found")),
+    }
+}
+
+fn main() {
+    let path = env::var("SUDOKU_DB").unwrap_or_else(|_| "leaderboard.db".to_string());
+    let addr = env::var("SUDOKU_ADDR").unwrap_or_else(|_| "0.0.0.0:8080".to_string());
+    let db = Connection::open(&path).unwrap_or_else(|e| panic!("{}: {}", path, e));
+    db.execute_batch(SCHEMA).expect("can't create the tables");
+    let server = Server::http(&addr).unwrap_or_else(|e| panic!("{}: {}", addr, e));
+    println!("leaderboard-server: {} on {}", path, addr);
+
+    let mut dailies = Dailies::new();
+    for mut request in server.incoming_requests() {
+        let (status, body) = handle(&db, &mut dailies, &mut request);
+        println!("{} {} {}", request.method(), request.url(), status);
+        let headers = [
+            "Content-Type: application/json",
+            // the game page is served by nginx, on another port in development
+            "Access-Control-Allow-Origin: *",
+            "Access-Control-Allow-Methods: GET, POST, OPTIONS",
+            "Access-Control-Allow-Headers: Content-Type",
+        ];
+        let mut response = Response::from_string(body).with_status_code(status);
+        for header in headers.iter() {
+            response.add_header(header.parse::<Header>().unwrap());
+        }
+        if let Err(e) = request.respond(response) {
+            eprintln!("leaderboard-server: {}", e);
+        }
+    }
+}
@@ -0,0 +1,143 @@
+//! Generates puzzles without opening the game window.
+//!
+//! sudoku-gen [--help] [--count N] [--difficult super-easy|easy|medium|hard] [--seed S]
+//!            [--size 4|6|9|12|16] [--variant classic|diagonal|windoku]
+//!            [--not-unique] [--rate] [--json]
+//!
+//! Every puzzle is one line: 81 characters for 9x9 with "." for empty cells,
+//! or a JSON object with --json. Puzzle n is built from seed S + n, so the
+//! same arguments always give the same puzzles.
+//!
+//! --difficult only sets how many givens are left. --rate adds the difficulty
+//! by the hardest technique a solver needs, the one the game shows for
+//! edited and loaded puzzles, so a puzzle with few givens may still rate easy.
+
+use std::process;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use sudoku_vk::game::generator::{self, Chess, Difficult, Variant};
+use sudoku_vk::game::{notation, rating};
+
+struct Options {
+    count: u64,
+    difficult: Difficult,
+    seed: u64,
+    size: usize,
+    variant: Variant,
+    is_unique: bool,
+    rate: bool,
+    json: bool,
+}
+
+const USAGE: &str = "usage: sudoku-gen [--help] [--count N] [--difficult super-easy|easy|medium|hard] [--seed S] [--size 4|6|9|12|16] [--variant classic|diagonal|windoku] [--not-unique] [--rate] [--json]
+
+--difficult only sets how many givens are left. --rate adds the difficulty
+by the hardest technique needed, so the two may disagree.";
+
+fn usage(error: &str) -> ! {
+    eprintln!("sudoku-gen: {}", error);
+    eprintln!("{}", USAGE);
+    process::exit(2);
+}
+
+fn parse_args() -> Options {
+    let mut options = Options {
+        count: 1,
+        difficult: Difficult::Medium,
+        seed: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64,
+        size: 9,
+        variant: Variant::Classic,
+        is_unique: true,
+        rate: false,
+        json: false,
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--help" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            }
+            "--not-unique" => { options.is_unique = false; }
+            "--rate" => { options.rate = true; }
+            "--json" => { options.json = true; }
+            "--count" | "--difficult" | "--seed" | "--size" | "--variant" => {
+                let value = match args.next() {
+                    None => usage(&format!("{} needs a value", arg)),
+                    Some(value) => value,
+                };
+                match arg.as_str() {
+                    "--count" => { options.count = value.parse().unwrap_or_else(|_| usage("bad --count")); }
+                    "--seed" => { options.seed = value.parse().unwrap_or_else(|_| usage("bad --seed")); }
+                    "--size" => {
+                        options.size = match value.parse() {
+                            Ok(size) if [4, 6, 9, 12, 16].contains(&size) => size,
+                            _ => usage("--size is one of 4, 6, 9, 12, 16"),
+                        };
+                    }
+                    "--difficult" => {
+                        options.difficult = match value.as_str() {
+                            "super-easy" => Difficult::SuperEasy,
+                            "easy" => Difficult::Easy,
+                            "medium" => Difficult::Medium,
+                            "hard" => Difficult::Hard,
+                            _ => usage("bad --difficult"),
+                        };
+                    }
+                    _ => {
+                        options.variant = match value.as_str() {
+                            "classic" => Variant::Classic,
+                            "diagonal" => Variant::Diagonal,
+                            "windoku" => Variant::Windoku,
+                            _ => usage("--variant is one of classic, diagonal, windoku"),
+                        };
+                    }
+                }
+            }
+            _ => usage(&format!("unknown argument {}", arg)),
+        }
+    }
+    if !generator::is_supported(options.variant, Chess::Off, options.size) {
+        usage("only classic puzzles come in sizes other than 9");
+    }
+    return options;
+}
+
+fn main() {
+    let options = parse_args();
+    for n in 0..options.count {
+        let seed = options.seed.wrapping_add(n);
+        let generated = match generator::generate(options.variant, Chess::Off, options.size, options.difficult, seed, options.is_unique) {
+            None => {
+                eprintln!("sudoku-gen: seed {} makes no puzzle", seed);
+                process::exit(1);
+            }
+            Some(generated) => generated,
+        };
+        let puzzle = notation::to_line(&generated.puzzle);
+        // without uniqueness the hardest step may be a guess between solutions
+        let rating = match options.rate {
+            false => None,
+            true => rating::hardest(&generated.rules, &generated.puzzle).map(Difficult::by_technique),
+        };
+        if !options.json {
+            match rating {
+                None => println!("{}", puzzle),
+                Some(rating) => println!("{} {}", puzzle, rating.name()),
+            }
+            continue;
+        }
+        let mut fields = vec![
+            format!("\"seed\":{}", seed),
+            format!("\"variant\":\"{}\"", options.variant.name()),
+            format!("\"size\":{}", options.size),
+            format!("\"difficult\":\"{}\"", options.difficult.name()),
+            format!("\"puzzle\":\"{}\"", puzzle),
+            format!("\"solution\":\"{}\"", notation::to_line(&generated.solution)),
+        ];
+        if let Some(rating) = rating {
+            fields.push(format!("\"rating\":\"{}\"", rating.name()));
+        }
+        println!("{{{}}}", fields.join(","));
+    }
+}
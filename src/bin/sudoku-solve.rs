@@ -0,0 +1,114 @@
+//! Solves and rates puzzles without opening the game window.
+//!
+//! sudoku-solve [--no-path] [FILE]
+//!
+//! Reads one puzzle per line from FILE or stdin, in the format sudoku-gen
+//! writes: 81 characters for 9x9, "." or "0" for empty cells. Empty lines and
+//! lines starting with "#" are skipped. For every puzzle prints the number of
+//! solutions, the solution, the difficulty and the steps a person would take.
+//!
+//! Exit code 1 when some puzzle can't be read or has no solution, 3 when
+//! some puzzle has more than one solution, 2 for bad arguments.
+
+use std::fs;
+use std::io::{self, Read};
+use std::process;
+
+use sudoku_vk::game::generator::Difficult;
+use sudoku_vk::game::notation;
+use sudoku_vk::game::rating;
+use sudoku_vk::game::solver::Rules;
+
+const INVALID: i32 = 1;
+const AMBIGUOUS: i32 = 3;
+
+fn usage(error: &str) -> ! {
+    eprintln!("sudoku-solve: {}", error);
+    eprintln!("usage: sudoku-solve [--no-path] [FILE]");
+    process::exit(2);
+}
+
+/// Prints the report of one puzzle and returns its exit code.
+fn solve(line: &str, show_path: bool) -> i32 {
+    println!("puzzle: {}", line);
+    let (size, grid) = match notation::from_line(line) {
+        None => {
+            println!("error: not a puzzle, expected 16, 36, 81, 144 or 256 cells of 1-9, A-G or .");
+            return INVALID;
+        }
+        Some(puzzle) => puzzle,
+    };
+    let rules = Rules::classic(size);
+    if !rules.conflicts(&grid).is_empty() {
+        println!("error: givens repeat in a row, column or box");
+        return INVALID;
+    }
+    match rules.count_solutions(&grid, 2) {
+        0 => {
+            println!("solutions: 0");
+            return INVALID;
+        }
+        1 => { println!("solutions: 1"); }
+        _ => {
+            println!("solutions: 2 or more");
+            return AMBIGUOUS;
+        }
+    }
+    let steps = rating::solve_path(&rules, &grid).unwrap();
+    let mut solution = grid.clone();
+    for step in &steps {
+        if let Some(cell) = step.cell {
+            solution[cell] = step.num;
+        }
+    }
+    println!("solution: {}", notation::to_line(&solution));
+    let hardest = steps.iter().map(|s| s.technique).max().unwrap_or(rating::Technique::NakedSingle);
+    println!("rating: {} ({})", Difficult::by_technique(hardest).name(), hardest.name());
+    if show_path {
+        println!("path:");
+        for step in &steps {
+            println!("  {}", step.describe(size));
+        }
+    }
+    return 0;
+}
+
+fn main() {
+    let mut show_path = true;
+    let mut file: Option<String> = None;
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--no-path" => { show_path = false; }
+            _ if arg.starts_with("--") => usage(&format!("unknown argument {}", arg)),
+            _ if file.is_none() => { file = Some(arg); }
+            _ => usage("only one file can be given"),
+        }
+    }
+    let input = match file {
+        None => {
+            let mut input = String::new();
+            io::stdin().read_to_string(&mut input).unwrap_or_else(|e| usage(&e.to_string()));
+            input
+        }
+        Some(path) => fs::read_to_string(&path).unwrap_or_else(|e| usage(&format!("{}: {}", path, e))),
+    };
+    let mut code = 0;
+    for line in input.lines().map(|l| l.trim()) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        // sudoku-gen --rate puts the rating after the puzzle
+        let puzzle = line.split_whitespace().next().unwrap();
+        match solve(puzzle, show_path) {
+            0 => {}
+            INVALID => { code = INVALID; }
+            c => {
+                if code == 0 {
+                    code = c;
+                }
+            }
+        }
+        println!();
+    }
+    process::exit(code);
+}
@@ -0,0 +1,441 @@
+//! Plays sudoku in a terminal, for example over SSH.
+//!
+//! sudoku-tui [--difficult super-easy|easy|medium|hard] [--seed S]
+//!            [--size 4|6|9|12|16] [--variant classic|diagonal|windoku|jigsaw]
+//!            [--mistakes 3|5]
+//!
+//! Plays the same `game::model::Model` as the game window: the generator,
+//! scoring, mistake limit and hints are shared. The terminal is switched to
+//! raw mode with stty, so this works on unix only.
+
+use std::io::{self, Read, Write};
+use std::process::{self, Command, Stdio};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use sudoku_vk::game::generator::{self, Chess, Difficult, Variant};
+use sudoku_vk::game::model::Model;
+
+const SYMBOLS: &str = "123456789ABCDEFG";
+// Four-way junctions by heavy arms: up 8, down 4, left 2, right 1.
+const CROSSES: [char; 16] = ['┼', '┾', '┽', '┿', '╁', '╆', '╅', '╈', '╀', '╄', '╃', '╇', '╂', '╊', '╉', '╋'];
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+const CYAN: &str = "\x1b[36m";
+const RED: &str = "\x1b[31m";
+const YELLOW: &str = "\x1b[33m";
+const REVERSE: &str = "\x1b[7m";
+const TINT: &str = "\x1b[48;5;237m";
+
+struct Options {
+    difficult: Difficult,
+    seed: u64,
+    size: usize,
+    variant: Variant,
+    mistake_limit: Option<u8>,
+}
+
+#[derive(Clone, Copy)]
+enum Input {
+    Up,
+    Down,
+    Left,
+    Right,
+    Erase,
+    Char(char),
+    Quit,
+}
+
+/// Raw mode and the alternate screen while alive, the terminal is restored on drop.
+struct Terminal {
+    saved: String,
+}
+
+impl Terminal {
+    fn open() -> Option<Self> {
+        let saved = stty(&["-g"])?;
+        // reads return after half a second without input, so the timer keeps going
+        stty(&["raw", "-echo", "min", "0", "time", "5"])?;
+        print!("\x1b[?1049h\x1b[?25l");
+        return Some(Terminal { saved: saved.trim().to_string() });
+    }
+
+    fn read(&self) -> Option<Input> {
+        let mut buf = [0u8; 8];
+        let n = io::stdin().read(&mut buf).unwrap_or(0);
+        return match &buf[..n] {
+            [] => None,
+            [27, b'[', b'A', ..] => Some(Input::Up),
+            [27, b'[', b'B', ..] => Some(Input::Down),
+            [27, b'[', b'C', ..] => Some(Input::Right),
+            [27, b'[', b'D', ..] => Some(Input::Left),
+            [27, b'[', b'3', b'~', ..] => Some(Input::Erase),
+            [127, ..] | [8, ..] => Some(Input::Erase),
+            [3, ..] => Some(Input::Quit),
+            [c, ..] if c.is_ascii() && *c != 27 => Some(Input::Char(*c as char)),
+            _ => None,
+        };
+    }
+
+    fn draw(&self, frame: &str) {
+        let mut out = String::from("\x1b[H");
+        for line in frame.lines() {
+            out.push_str(line);
+            out.push_str("\x1b[K\r\n");
+        }
+        out.push_str("\x1b[J");
+        let mut stdout = io::stdout();
+        let _ = stdout.write_all(out.as_bytes());
+        let _ = stdout.flush();
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        print!("\x1b[?25h\x1b[?1049l");
+        let _ = io::stdout().flush();
+        stty(&[self.saved.as_str()]);
+    }
+}
+
+fn stty(args: &[&str]) -> Option<String> {
+    let output = Command::new("stty").args(args).stdin(Stdio::inherit()).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    return Some(String::from_utf8_lossy(&output.stdout).to_string());
+}
+
+/// Draws a `Model` and feeds it the keys, the rules and scoring are the
+/// game window's.
+struct Tui {
+    model: Model,
+    cursor: usize,
+    is_notes: bool,
+    message: String,
+}
+
+impl Tui {
+    fn new(options: &Options) -> Option<Self> {
+        let mut model = Model::new(options.variant, Chess::Off, options.size, options.difficult, options.seed)?;
+        model.mistake_limit = options.mistake_limit;
+        let grid = model.current_grid();
+        let cursor = (0..grid.len()).find(|&c| grid[c] == 0 && model.rules.active[c]).unwrap_or(0);
+        let mut tui = Tui { model, cursor, is_notes: false, message: String::new() };
+        tui.select();
+        return Some(tui);
+    }
+
+    fn width(&self) -> usize {
+        return self.model.width;
+    }
+
+    fn coord(&self) -> (usize, usize) {
+        return (self.cursor % self.width(), self.cursor / self.width());
+    }
+
+    fn select(&mut self) {
+        let (x, y) = self.coord();
+        self.model.select(x, y);
+    }
+
+    fn move_cursor(&mut self, dx: isize, dy: isize) {
+        let width = self.width() as isize;
+        let x = (self.cursor as isize % width + dx + width) % width;
+        let y = (self.cursor as isize / width + dy + width) % width;
+        self.cursor = (y * width + x) as usize;
+        self.select();
+    }
+
+    fn handle(&mut self, input: Input) -> bool {
+        if let Input::Quit = input {
+            return false;
+        }
+        if self.model.is_paused {
+            match input {
+                Input::Char('q') => { return false; }
+                Input::Char('p') => {
+                    self.model.resume();
+                    // pausing drops the selection
+                    self.select();
+                }
+                _ => {}
+            }
+            return true;
+        }
+        match input {
+            Input::Up | Input::Char('k') => self.move_cursor(0, -1),
+            Input::Down | Input::Char('j') => self.move_cursor(0, 1),
+            Input::Left | Input::Char('h') => self.move_cursor(-1, 0),
+            Input::Right | Input::Char('l') => self.move_cursor(1, 0),
+            Input::Erase | Input::Char('x') | Input::Char('0') | Input::Char('.') => {
+                let (x, y) = self.coord();
+                self.model.erase_num(x, y);
+                self.model.no_valid.retain(|c| *c != [x, y]);
+            }
+            Input::Char('q') => { return false; }
+            Input::Char('n') => { self.is_notes = !self.is_notes; }
+            Input::Char('p') => self.model.pause(),
+            Input::Char('?') => {
+                self.model.hint();
+                if let Some([x, y]) = self.model.selected() {
+                    self.cursor = y * self.width() + x;
+                }
+            }
+            Input::Char('v') => {
+                self.model.validate();
+                self.message = match self.model.no_valid.len() {
+                    0 => "Ошибок нет".to_string(),
+                    n => format!("Ошибок: {}", n),
+                };
+            }
+            Input::Char(c) => {
+                match SYMBOLS.chars().position(|s| s == c.to_ascii_uppercase()) {
+                    Some(i) if i < self.model.size => self.place_num(i as u8 + 1),
+                    _ => {}
+                }
+            }
+            Input::Quit => {}
+        }
+        self.check_end();
+        return true;
+    }
+
+    fn place_num(&mut self, num: u8) {
+        let (x, y) = self.coord();
+        if self.is_notes {
+            self.model.toggle_note(x, y, num);
+            return;
+        }
+        self.model.place_num(x, y, num);
+        self.model.no_valid.retain(|c| *c != [x, y]);
+    }
+
+    fn check_end(&mut self) {
+        if self.model.is_finish || self.model.is_lost {
+            return;
+        }
+        if self.model.is_win() {
+            self.is_notes = false;
+            self.message = format!("Решено за {}! r — новая игра, q — выход", self.model.get_duration());
+        }
+        if self.model.is_lost {
+            self.message = "Игра окончена. r — новая игра, q — выход".to_string();
+        }
+    }
+
+    fn is_tinted(&self, cell: usize) -> bool {
+        let (x, y) = (cell % self.width(), cell / self.width());
+        return match self.model.current_variant {
+            Variant::Diagonal => x == y || x + y == self.width() - 1,
+            // the extra boxes are everything between rows and columns 0, 4 and 8
+            Variant::Windoku => x % 4 != 0 && y % 4 != 0,
+            _ => false,
+        };
+    }
+
+    fn draw_cell(&self, grid: &[u8], cell: usize, out: &mut String) {
+        let num = grid[cell];
+        let (x, y) = (cell % self.width(), cell / self.width());
+        let is_paused = self.model.is_paused;
+        let mut style = String::new();
+        if self.is_tinted(cell) {
+            style.push_str(TINT);
+        }
+        let symbol = match num {
+            _ if is_paused => ' ',
+            0 if self.model.notes(x, y) != 0 => {
+                style.push_str(DIM);
+                '·'
+            }
+            0 => ' ',
+            n => {
+                let is_given = self.model.is_given(x, y);
+                if is_given {
+                    style.push_str(BOLD);
+                }
+                if self.model.no_valid.contains(&[x, y]) {
+                    style.push_str(RED);
+                } else if n == grid[self.cursor] && cell != self.cursor {
+                    style.push_str(YELLOW);
+                } else if !is_given {
+                    style.push_str(CYAN);
+                }
+                SYMBOLS.chars().nth(n as usize - 1).unwrap()
+            }
+        };
+        if cell == self.cursor && !is_paused {
+            style.push_str(REVERSE);
+        }
+        out.push_str(&format!("{} {} {}", style, symbol, RESET));
+    }
+
+    fn draw_grid(&self, out: &mut String) {
+        let width = self.width();
+        let regions = &self.model.rules.regions;
+        let grid = self.model.current_grid();
+        // a line is heavy on the border and between regions
+        let heavy_row = |x: usize, y: usize| y == 0 || y == width || regions[(y - 1) * width + x] != regions[y * width + x];
+        let heavy_column = |x: usize, y: usize| x == 0 || x == width || regions[y * width + x - 1] != regions[y * width + x];
+        for y in 0..=width {
+            for x in 0..=width {
+                let up = y > 0 && heavy_column(x, y - 1);
+                let down = y < width && heavy_column(x, y);
+                let left = x > 0 && heavy_row(x - 1, y);
+                let right = x < width && heavy_row(x, y);
+                let junction = match (x, y) {
+                    (0, 0) => '┏',
+                    (0, _) if y == width => '┗',
+                    (_, 0) if x == width => '┓',
+                    _ if x == width && y == width => '┛',
+                    (_, 0) => if down { '┳' } else { '┯' },
+                    _ if y == width => if up { '┻' } else { '┷' },
+                    (0, _) => if right { '┣' } else { '┠' },
+                    _ if x == width => if left { '┫' } else { '┨' },
+                    _ => CROSSES[up as usize * 8 + down as usize * 4 + left as usize * 2 + right as usize],
+                };
+                out.push(junction);
+                if x < width {
+                    out.push_str(if heavy_row(x, y) { "━━━" } else { "───" });
+                }
+            }
+            out.push('\n');
+            if y == width {
+                break;
+            }
+            for x in 0..width {
+                out.push(if heavy_column(x, y) { '┃' } else { '│' });
+                self.draw_cell(&grid, y * width + x, out);
+            }
+            out.push_str("┃\n");
+        }
+    }
+
+    fn frame(&self) -> String {
+        let model = &self.model;
+        let mut out = String::new();
+        let mut status = vec![
+            format!("Время: {}", model.get_duration()),
+            model.get_score(),
+            format!("Подсказки: {}", model.hints_used),
+            format!("Проверки: {}", model.checks_used),
+        ];
+        status.extend(model.get_mistakes());
+        out.push_str(&format!("{}\n\n", status.join("   ")));
+        self.draw_grid(&mut out);
+        out.push('\n');
+        let mode = if self.is_notes { "заметки" } else { "цифры" };
+        let (x, y) = self.coord();
+        let notes: Vec<String> = (1..=model.size)
+            .filter(|&n| model.notes(x, y) & (1 << n) != 0)
+            .map(|n| SYMBOLS.chars().nth(n - 1).unwrap().to_string())
+            .collect();
+        out.push_str(&format!("Ввод: {}   Заметки: {}\n", mode, notes.join(" ")));
+        if model.is_paused {
+            out.push_str("Пауза, p — продолжить\n");
+        } else {
+            out.push_str(&format!("{}\n", self.message));
+        }
+        out.push_str(&format!(
+            "{}стрелки/hjkl — ход, 1-{} — цифра, n — заметки, x — стереть,\n? — подсказка, v — проверка, p — пауза, r — новая игра, q — выход{}\n",
+            DIM,
+            SYMBOLS.chars().nth(model.size - 1).unwrap().to_ascii_lowercase(),
+            RESET,
+        ));
+        return out;
+    }
+}
+
+fn usage(error: &str) -> ! {
+    eprintln!("sudoku-tui: {}", error);
+    eprintln!("usage: sudoku-tui [--difficult super-easy|easy|medium|hard] [--seed S] [--size 4|6|9|12|16] [--variant classic|diagonal|windoku|jigsaw] [--mistakes 3|5]");
+    process::exit(2);
+}
+
+fn parse_args() -> Options {
+    let mut options = Options {
+        difficult: Difficult::Medium,
+        seed: SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64,
+        size: 9,
+        variant: Variant::Classic,
+        mistake_limit: None,
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let value = match args.next() {
+            None => usage(&format!("{} needs a value", arg)),
+            Some(value) => value,
+        };
+        match arg.as_str() {
+            "--seed" => { options.seed = value.parse().unwrap_or_else(|_| usage("bad --seed")); }
+            "--size" => {
+                options.size = match value.parse() {
+                    Ok(size) if [4, 6, 9, 12, 16].contains(&size) => size,
+                    _ => usage("--size is one of 4, 6, 9, 12, 16"),
+                };
+            }
+            "--difficult" => {
+                options.difficult = match value.as_str() {
+                    "super-easy" => Difficult::SuperEasy,
+                    "easy" => Difficult::Easy,
+                    "medium" => Difficult::Medium,
+                    "hard" => Difficult::Hard,
+                    _ => usage("bad --difficult"),
+                };
+            }
+            "--variant" => {
+                options.variant = match value.as_str() {
+                    "classic" => Variant::Classic,
+                    "diagonal" => Variant::Diagonal,
+                    "windoku" => Variant::Windoku,
+                    "jigsaw" => Variant::Jigsaw,
+                    _ => usage("--variant is one of classic, diagonal, windoku, jigsaw"),
+                };
+            }
+            "--mistakes" => {
+                options.mistake_limit = match value.parse() {
+                    Ok(limit) if limit == 3 || limit == 5 => Some(limit),
+                    _ => usage("--mistakes is 3 or 5"),
+                };
+            }
+            _ => usage(&format!("unknown argument {}", arg)),
+        }
+    }
+    if !generator::is_supported(options.variant, Chess::Off, options.size) {
+        usage("only classic puzzles come in sizes other than 9");
+    }
+    return options;
+}
+
+fn main() {
+    let mut options = parse_args();
+    println!("Генерация...");
+    let mut tui = Tui::new(&options).unwrap_or_else(|| usage("no puzzle fits these settings"));
+    let terminal = match Terminal::open() {
+        None => usage("stdin is not a terminal"),
+        Some(terminal) => terminal,
+    };
+    let mut last_tick = Instant::now();
+    loop {
+        let now = Instant::now();
+        tui.model.tick((now - last_tick).as_secs_f64());
+        // there is no page to pass the events to
+        tui.model.take_events();
+        last_tick = now;
+        terminal.draw(&tui.frame());
+        match terminal.read() {
+            None => {}
+            Some(Input::Char('r')) if !tui.model.is_paused => {
+                terminal.draw("Генерация...");
+                options.seed = options.seed.wrapping_add(1);
+                if let Some(new) = Tui::new(&options) {
+                    tui = new;
+                }
+                last_tick = Instant::now();
+            }
+            Some(input) if !tui.handle(input) => { break; }
+            Some(_) => {}
+        }
+    }
+}
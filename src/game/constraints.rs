@@ -0,0 +1,170 @@
+/// Killer cage: digits inside don't repeat and add up to `sum`.
+#[derive(Clone, Debug)]
+pub struct Cage {
+    pub cells: Vec<usize>,
+    pub sum: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Relation {
+    // the digit in `a` is bigger than the one in `b`
+    Greater,
+    // Kropki white dot: the digits differ by one
+    Consecutive,
+    // Kropki black dot: one digit is twice the other
+    Double,
+    // neighbours without a Kropki dot are neither consecutive nor double
+    NoDot,
+}
+
+/// Relation between two neighbouring cells.
+#[derive(Clone, Debug)]
+pub struct Pair {
+    pub a: usize,
+    pub b: usize,
+    pub relation: Relation,
+}
+
+impl Pair {
+    pub fn allows(&self, a: u8, b: u8) -> bool {
+        let is_consecutive = a + 1 == b || b + 1 == a;
+        let is_double = a * 2 == b || b * 2 == a;
+        match self.relation {
+            Relation::Greater => a > b,
+            Relation::Consecutive => is_consecutive,
+            // 1 and 2 are both, such pairs always get the white dot
+            Relation::Double => is_double && !is_consecutive,
+            Relation::NoDot => !is_consecutive && !is_double,
+        }
+    }
+}
+
+/// A rule over some cells on top of the units, where digits just can't repeat.
+/// A new kind of variant only needs a new constraint here: the solver asks
+/// it which digits a cell can take and which filled cells break it.
+#[derive(Clone, Debug)]
+pub enum Constraint {
+    Cage(Cage),
+    Pair(Pair),
+}
+
+impl Constraint {
+    pub fn cells(&self) -> Vec<usize> {
+        match self {
+            Constraint::Cage(cage) => cage.cells.clone(),
+            Constraint::Pair(pair) => vec![pair.a, pair.b],
+        }
+    }
+
+    /// Whether putting `num` into the empty `cell` leaves a way to satisfy the
+    /// constraint, digits go from 1 to `size`.
+    pub fn allows(&self, grid: &[u8], size: usize, cell: usize, num: u8) -> bool {
+        match self {
+            Constraint::Cage(cage) => Constraint::cage_allows(cage, grid, size, num),
+            Constraint::Pair(pair) => {
+                let other = if pair.a == cell { pair.b } else { pair.a };
+                let others: Vec<u8> = match grid[other] {
+                    0 => (1..=size as u8).filter(|&n| n != num).collect(),
+                    v => vec![v],
+                };
+                others.into_iter().any(|v| match pair.a == cell {
+                    true => pair.allows(num, v),
+                    false => pair.allows(v, num),
+                })
+            }
+        }
+    }
+
+    fn cage_allows(cage: &Cage, grid: &[u8], size: usize, num: u8) -> bool {
+        let mut used: u32 = 1 << num;
+        let mut sum = num as u32;
+        let mut empty = 0;
+        for &cell in &cage.cells {
+            match grid[cell] {
+                0 => { empty += 1 }
+                v => {
+                    used |= 1 << v;
+                    sum += v as u32;
+                }
+            }
+        }
+        // the cell `num` goes to is one of the empty ones, there is no room
+        // for it in a full cage
+        let left = match empty {
+            0 => { return false; }
+            _ => empty - 1,
+        };
+        if sum > cage.sum {
+            return false;
+        }
+        let free: Vec<u32> = (1..=size as u32).filter(|n| used & (1 << n) == 0).collect();
+        if free.len() < left {
+            return false;
+        }
+        let min: u32 = free.iter().take(left).sum();
+        let max: u32 = free.iter().rev().take(left).sum();
+        return sum + min <= cage.sum && cage.sum <= sum + max;
+    }
+
+    /// Filled cells of a broken constraint, empty while it can still hold.
+    pub fn broken(&self, grid: &[u8]) -> Vec<usize> {
+        let is_broken = match self {
+            Constraint::Cage(cage) => {
+                let sum: u32 = cage.cells.iter().map(|&c| grid[c] as u32).sum();
+                let is_full = cage.cells.iter().all(|&c| grid[c] != 0);
+                sum > cage.sum || (is_full && sum != cage.sum)
+            }
+            Constraint::Pair(pair) => {
+                grid[pair.a] != 0 && grid[pair.b] != 0 && !pair.allows(grid[pair.a], grid[pair.b])
+            }
+        };
+        if !is_broken {
+            return vec![];
+        }
+        return self.cells().into_iter().filter(|&c| grid[c] != 0).collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pair(relation: Relation) -> Pair {
+        return Pair { a: 0, b: 1, relation };
+    }
+
+    #[test]
+    fn pair_relations() {
+        assert!(pair(Relation::Greater).allows(3, 2));
+        assert!(!pair(Relation::Greater).allows(2, 3));
+        assert!(pair(Relation::Consecutive).allows(4, 5));
+        assert!(!pair(Relation::Consecutive).allows(4, 6));
+        assert!(pair(Relation::Double).allows(3, 6));
+        assert!(!pair(Relation::Double).allows(3, 5));
+        // 1 and 2 get the white dot, not the black one
+        assert!(!pair(Relation::Double).allows(1, 2));
+        assert!(pair(Relation::NoDot).allows(2, 5));
+        assert!(!pair(Relation::NoDot).allows(2, 3));
+        assert!(!pair(Relation::NoDot).allows(2, 4));
+    }
+
+    #[test]
+    fn cage_sums() {
+        let cage = Cage { cells: vec![0, 1, 2], sum: 6 };
+        let empty = vec![0; 16];
+        // 1 2 3 is the only way to make 6 out of three digits
+        assert!(Constraint::cage_allows(&cage, &empty, 4, 1));
+        assert!(Constraint::cage_allows(&cage, &empty, 4, 3));
+        assert!(!Constraint::cage_allows(&cage, &empty, 4, 4));
+
+        let mut grid = empty.clone();
+        grid[0] = 1;
+        grid[1] = 2;
+        assert!(Constraint::cage_allows(&cage, &grid, 4, 3));
+        assert!(!Constraint::cage_allows(&cage, &grid, 4, 4));
+        assert!(!Constraint::cage_allows(&Cage { cells: vec![0, 1, 2], sum: 5 }, &grid, 4, 3));
+
+        grid[2] = 3;
+        assert!(!Constraint::cage_allows(&cage, &grid, 4, 3));
+    }
+}
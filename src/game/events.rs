@@ -0,0 +1,84 @@
+use crate::game::generator::{Chess, Difficult, Variant};
+use crate::game::replay::Move;
+
+/// Things the page around the game wants to know about. The game collects
+/// them while playing and the wasm host passes them on as JSON.
+#[derive(Clone, Debug)]
+pub enum Event {
+    Started { difficult: Difficult, variant: Variant, size: usize },
+    // `seed` builds the puzzle again with `generator::generate`, None for
+    // puzzles from the editor or the page. With `moves` the leaderboard
+    // server checks the solve, see `game::verify`.
+    Won {
+        difficult: Difficult,
+        variant: Variant,
+        chess: Chess,
+        size: usize,
+        seed: Option<u64>,
+        // UTC day of the daily puzzle, None for any other game
+        day: Option<i64>,
+        elapsed: f64,
+        score: i64,
+        mistakes: u8,
+        hints: u32,
+        moves: Vec<Move>,
+    },
+    // the mistake limit was reached
+    Lost { difficult: Difficult, elapsed: f64 },
+    HintUsed { hints: u32 },
+    // the player left an unfinished game
+    Abandoned { difficult: Difficult, elapsed: f64 },
+}
+
+impl Event {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Event::Started { .. } => "started",
+            Event::Won { .. } => "won",
+            Event::Lost { .. } => "lost",
+            Event::HintUsed { .. } => "hint_used",
+            Event::Abandoned { .. } => "abandoned",
+        }
+    }
+
+    /// One JSON object, the event name is in "type".
+    pub fn to_json(&self) -> String {
+        let mut fields = vec![format!("\"type\":\"{}\"", self.name())];
+        match self {
+            Event::Started { difficult, variant, size } => {
+                fields.push(format!("\"difficult\":\"{}\"", difficult.name()));
+                fields.push(format!("\"variant\":\"{}\"", variant.name()));
+                fields.push(format!("\"size\":{}", size));
+            }
+            Event::Won { difficult, variant, chess, size, seed, day, elapsed, score, mistakes, hints, moves } => {
+                fields.push(format!("\"difficult\":\"{}\"", difficult.name()));
+                fields.push(format!("\"variant\":\"{}\"", variant.name()));
+                fields.push(format!("\"chess\":\"{}\"", chess.name()));
+                fields.push(format!("\"size\":{}", size));
+                match seed {
+                    // as a string, JavaScript numbers lose precision above 2^53
+                    Some(seed) => fields.push(format!("\"seed\":\"{}\"", seed)),
+                    None => fields.push("\"seed\":null".to_string()),
+                }
+                match day {
+                    Some(day) => fields.push(format!("\"day\":{}", day)),
+                    None => fields.push("\"day\":null".to_string()),
+                }
+                fields.push(format!("\"elapsed\":{:.1}", elapsed));
+                fields.push(format!("\"score\":{}", score));
+                fields.push(format!("\"mistakes\":{}", mistakes));
+                fields.push(format!("\"hints\":{}", hints));
+                let moves: Vec<String> = moves.iter().map(|m| m.to_json()).collect();
+                fields.push(format!("\"moves\":[{}]", moves.join(",")));
+            }
+            Event::Lost { difficult, elapsed } | Event::Abandoned { difficult, elapsed } => {
+                fields.push(format!("\"difficult\":\"{}\"", difficult.name()));
+                fields.push(format!("\"elapsed\":{:.1}", elapsed));
+            }
+            Event::HintUsed { hints } => {
+                fields.push(format!("\"hints\":{}", hints));
+            }
+        }
+        return format!("{{{}}}", fields.join(","));
+    }
+}
@@ -0,0 +1,253 @@
+use crate::game::rating::Technique;
+use crate::game::solver::{Rng, Rules, KING_MOVES, KNIGHT_MOVES};
+
+// Random jigsaw regions sometimes can't be filled at all, new regions are
+// tried this many times.
+const JIGSAW_ATTEMPTS: usize = 20;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Difficult {
+    SuperEasy,
+    Easy,
+    Medium,
+    Hard,
+}
+
+impl Difficult {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficult::SuperEasy => "super-easy",
+            Difficult::Easy => "easy",
+            Difficult::Medium => "medium",
+            Difficult::Hard => "hard",
+        }
+    }
+
+    pub fn by_name(name: &str) -> Option<Difficult> {
+        return [Difficult::SuperEasy, Difficult::Easy, Difficult::Medium, Difficult::Hard]
+            .iter()
+            .copied()
+            .find(|d| d.name() == name);
+    }
+
+    /// Difficulty of a puzzle that needs `technique` at its hardest step.
+    pub fn by_technique(technique: Technique) -> Difficult {
+        match technique {
+            Technique::NakedSingle => Difficult::SuperEasy,
+            Technique::HiddenSingle => Difficult::Easy,
+            Technique::NakedPair | Technique::Intersection => Difficult::Medium,
+            Technique::Guess => Difficult::Hard,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Variant {
+    Classic,
+    Diagonal,
+    Killer,
+    Jigsaw,
+    Samurai,
+    Windoku,
+    Inequality,
+    Kropki,
+}
+
+impl Variant {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Variant::Classic => "classic",
+            Variant::Diagonal => "diagonal",
+            Variant::Killer => "killer",
+            Variant::Jigsaw => "jigsaw",
+            Variant::Samurai => "samurai",
+            Variant::Windoku => "windoku",
+            Variant::Inequality => "inequality",
+            Variant::Kropki => "kropki",
+        }
+    }
+
+    pub fn by_name(name: &str) -> Option<Variant> {
+        return [
+            Variant::Classic,
+            Variant::Diagonal,
+            Variant::Killer,
+            Variant::Jigsaw,
+            Variant::Samurai,
+            Variant::Windoku,
+            Variant::Inequality,
+            Variant::Kropki,
+        ]
+            .iter()
+            .copied()
+            .find(|v| v.name() == name);
+    }
+}
+
+/// Chess rules on top of classic: the same digit can't be a knight's or king's move apart.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Chess {
+    Off,
+    Knight,
+    King,
+    Both,
+}
+
+impl Chess {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Chess::Off => "off",
+            Chess::Knight => "knight",
+            Chess::King => "king",
+            Chess::Both => "both",
+        }
+    }
+
+    pub fn by_name(name: &str) -> Option<Chess> {
+        return [Chess::Off, Chess::Knight, Chess::King, Chess::Both].iter().copied().find(|c| c.name() == name);
+    }
+}
+
+pub struct Generated {
+    pub rules: Rules,
+    pub solution: Vec<u8>,
+    pub puzzle: Vec<u8>,
+}
+
+/// The settings the game can start: chess rules and variants other than
+/// classic only come in 9x9.
+pub fn is_supported(variant: Variant, chess: Chess, size: usize) -> bool {
+    if ![4, 6, 9, 12, 16].contains(&size) {
+        return false;
+    }
+    return size == 9 || (variant == Variant::Classic && chess == Chess::Off);
+}
+
+/// Builds a puzzle from `seed`, the same arguments always give the same puzzle.
+/// Without `is_unique` digits are removed at random, which is much faster
+/// but the puzzle may have more than one solution. None when no grid fits
+/// the rules, like a 4x4 without kings next to each other.
+pub fn generate(variant: Variant, chess: Chess, size: usize, dif: Difficult, seed: u64, is_unique: bool) -> Option<Generated> {
+    let mut rng = Rng::new(seed);
+    let (rules, solution) = match variant {
+        Variant::Classic => {
+            let mut rules = Rules::classic(size);
+            if chess == Chess::Knight || chess == Chess::Both {
+                rules.add_moves(&KNIGHT_MOVES);
+            }
+            if chess == Chess::King || chess == Chess::Both {
+                rules.add_moves(&KING_MOVES);
+            }
+            let solution = rules.generate_solved(&mut rng)?;
+            (rules, solution)
+        }
+        Variant::Diagonal => {
+            let rules = Rules::diagonal(size);
+            let solution = rules.generate_solved(&mut rng)?;
+            (rules, solution)
+        }
+        Variant::Killer => {
+            let solution = Rules::classic(size).generate_solved(&mut rng)?;
+            let rules = Rules::killer(size, Rules::make_cages(size, &solution, &mut rng));
+            (rules, solution)
+        }
+        Variant::Jigsaw => {
+            let mut generated = None;
+            for _ in 0..JIGSAW_ATTEMPTS {
+                let rules = Rules::jigsaw(size, Rules::make_regions(size, &mut rng));
+                if let Some(solution) = rules.generate_solved(&mut rng) {
+                    generated = Some((rules, solution));
+                    break;
+                }
+            }
+            generated?
+        }
+        Variant::Samurai => {
+            let rules = Rules::samurai();
+            let solution = rules.generate_solved(&mut rng)?;
+            (rules, solution)
+        }
+        Variant::Windoku => {
+            let rules = Rules::windoku();
+            let solution = rules.generate_solved(&mut rng)?;
+            (rules, solution)
+        }
+        Variant::Inequality => {
+            let solution = Rules::classic(size).generate_solved(&mut rng)?;
+            let rules = Rules::pairwise(size, Rules::make_inequalities(size, &solution));
+            (rules, solution)
+        }
+        Variant::Kropki => {
+            let solution = Rules::classic(size).generate_solved(&mut rng)?;
+            let rules = Rules::pairwise(size, Rules::make_kropki(size, &solution));
+            (rules, solution)
+        }
+    };
+    let cells = rules.active.iter().filter(|a| **a).count();
+    let givens = givens_count(variant, cells, dif);
+    let puzzle = match is_unique {
+        true => rules.make_puzzle(&solution, givens, &mut rng),
+        false => {
+            let mut puzzle = solution.clone();
+            let mut order: Vec<usize> = (0..rules.cells()).filter(|&c| rules.active[c]).collect();
+            rng.shuffle(&mut order);
+            for &cell in order.iter().skip(givens) {
+                puzzle[cell] = 0;
+            }
+            puzzle
+        }
+    };
+    return Some(Generated { rules, solution, puzzle });
+}
+
+pub fn givens_count(variant: Variant, cells: usize, dif: Difficult) -> usize {
+    // cage sums and signs between cells carry most of the information,
+    // so these variants need far fewer digits
+    let percent = match (variant, dif) {
+        (Variant::Killer | Variant::Inequality | Variant::Kropki, Difficult::SuperEasy) => 37,
+        (Variant::Killer | Variant::Inequality | Variant::Kropki, Difficult::Easy) => 25,
+        (Variant::Killer | Variant::Inequality | Variant::Kropki, Difficult::Medium) => 10,
+        (Variant::Killer | Variant::Inequality | Variant::Kropki, Difficult::Hard) => 0,
+        (_, Difficult::SuperEasy) => 62,
+        (_, Difficult::Easy) => 49,
+        (_, Difficult::Medium) => 40,
+        (_, Difficult::Hard) => 30,
+    };
+    return cells * percent / 100;
+}
+
+/// Seed of the puzzle of the day, the same for every player who picks these
+/// settings. `day` counts UTC days since 1970-01-01.
+pub fn daily_seed(day: i64, variant: Variant, chess: Chess, size: usize, dif: Difficult) -> u64 {
+    let key = format!("{}/{}/{}/{}/{}", day, variant.name(), chess.name(), size, dif.name());
+    // FNV-1a, `Rng::new` scrambles it further
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in key.bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    return hash;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_grid_is_none() {
+        // every 4x4 has the same digit diagonally next to itself somewhere
+        assert!(generate(Variant::Classic, Chess::King, 4, Difficult::Easy, 1, true).is_none());
+        assert!(generate(Variant::Classic, Chess::Off, 4, Difficult::Easy, 1, true).is_some());
+    }
+
+    #[test]
+    fn supported_settings() {
+        assert!(is_supported(Variant::Classic, Chess::Off, 16));
+        assert!(is_supported(Variant::Jigsaw, Chess::Off, 9));
+        assert!(is_supported(Variant::Classic, Chess::Both, 9));
+        assert!(!is_supported(Variant::Classic, Chess::King, 4));
+        assert!(!is_supported(Variant::Windoku, Chess::Off, 6));
+        assert!(!is_supported(Variant::Classic, Chess::Off, 5));
+    }
+
+}
@@ -0,0 +1,671 @@
+//! The state and rules of one game without anything about drawing it. The
+//! macroquad window and sudoku-tui are both front-ends for `Model`.
+
+use std::collections::HashMap;
+
+use crate::game::events::Event;
+use crate::game::generator::{self, Chess, Difficult, Variant};
+use crate::game::notation;
+use crate::game::replay::{Action, Move};
+use crate::game::solver::Rules;
+use crate::game::stats::{Record, Statistics};
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub(crate) struct Key {
+    pub(crate) x: usize,
+    pub(crate) y: usize,
+}
+
+// Longest gap between two ticks still counted as play time, a longer one
+// means the game wasn't on screen.
+const MAX_FRAME_GAP: f64 = 1.0;
+
+const CELL_POINTS: i64 = 10;
+const MISTAKE_PENALTY: i64 = 20;
+const HINT_PENALTY: i64 = 50;
+const CHECK_PENALTY: i64 = 25;
+// The time bonus is a tenth of a point per second left of this many seconds,
+// times the difficulty multiplier.
+const TIME_BONUS_SECONDS: f64 = 1800.;
+
+pub struct Model {
+    pub current_difficult: Difficult,
+    pub current_variant: Variant,
+    pub chess: Chess,
+    pub size: usize,
+    pub seed: u64,
+    // the puzzle came from the editor or the page, `seed` can't build it again
+    is_custom: bool,
+    // UTC day while playing the puzzle of that day, see `play_daily`
+    pub day: Option<i64>,
+    pub elapsed: f64,
+    pub is_paused: bool,
+    pub is_finish: bool,
+    pub is_lost: bool,
+    pub mistake_limit: Option<u8>,
+    pub mistakes: u8,
+    pub score: i64,
+    pub hints_used: u32,
+    pub checks_used: u32,
+    pub stats: Statistics,
+    pub(crate) moves: Vec<Move>,
+    // events the page hasn't taken yet
+    events: Vec<Event>,
+    // board side in cells, differs from `size` only for samurai
+    pub width: usize,
+    pub rules: Rules,
+    // the solution, 0-based digits by row and column
+    pub(crate) matrix: Vec<Vec<u8>>,
+    // the player's digits, 1-based
+    pub(crate) user_matrix: HashMap<Key, u8>,
+    scored: HashMap<Key, bool>,
+    // cells without a given
+    pub(crate) empties: HashMap<Key, bool>,
+    pub(crate) marked_coord: Vec<[usize; 2]>,
+    // cells the last check found wrong
+    pub no_valid: Vec<[usize; 2]>,
+    // candidates the player wrote down by cell, bit n for digit n
+    pub(crate) notes: Vec<u32>,
+}
+
+impl Model {
+    /// None when the settings can't make a puzzle, see `generator::generate`.
+    pub fn new(variant: Variant, chess: Chess, size: usize, difficult: Difficult, seed: u64) -> Option<Self> {
+        let mut model = Model {
+            current_difficult: difficult,
+            current_variant: variant,
+            chess,
+            size,
+            seed,
+            is_custom: false,
+            day: None,
+            elapsed: 0.,
+            is_paused: false,
+            is_finish: false,
+            is_lost: false,
+            mistake_limit: None,
+            mistakes: 0,
+            score: 0,
+            hints_used: 0,
+            checks_used: 0,
+            stats: Statistics::new(),
+            moves: vec![],
+            events: vec![],
+            width: size,
+            rules: Rules::classic(size),
+            matrix: vec![],
+            user_matrix: Default::default(),
+            scored: Default::default(),
+            empties: Default::default(),
+            marked_coord: vec![],
+            no_valid: vec![],
+            notes: vec![],
+        };
+        if !model.generate(seed) {
+            return None;
+        }
+        return Some(model);
+    }
+
+    /// Builds a new puzzle from `seed` with the current variant, size and
+    /// difficulty, and starts it. False when the settings can't make one,
+    /// the board stays as it was then.
+    pub fn generate(&mut self, seed: u64) -> bool {
+        let generated = match generator::generate(self.current_variant, self.chess, self.size, self.current_difficult, seed, true) {
+            None => { return false; }
+            Some(generated) => generated,
+        };
+        self.seed = seed;
+        self.set_board(generated.rules, &generated.solution, &generated.puzzle);
+        self.is_custom = false;
+        self.day = None;
+        self.push_started();
+        return true;
+    }
+
+    /// Starts the puzzle of `day` with the current settings, its wins go to
+    /// the day's rankings.
+    pub fn play_daily(&mut self, day: i64) -> bool {
+        if !self.generate(generator::daily_seed(day, self.current_variant, self.chess, self.size, self.current_difficult)) {
+            return false;
+        }
+        self.day = Some(day);
+        return true;
+    }
+
+    /// Starts a puzzle that doesn't come from a seed, `solution` must solve it.
+    pub fn load(&mut self, rules: Rules, solution: &[u8], puzzle: &[u8], difficult: Difficult) {
+        self.current_variant = Variant::Classic;
+        self.chess = Chess::Off;
+        self.size = rules.size;
+        self.current_difficult = difficult;
+        self.set_board(rules, solution, puzzle);
+        self.is_custom = true;
+        self.day = None;
+        self.push_started();
+    }
+
+    /// Replaces the board without starting a game, 0 in `puzzle` is an empty cell.
+    pub(crate) fn set_board(&mut self, rules: Rules, solution: &[u8], puzzle: &[u8]) {
+        self.width = rules.width;
+        self.empties = Default::default();
+        for (i, num) in puzzle.iter().enumerate() {
+            if *num == 0 && rules.active[i] {
+                self.empties.insert(Key { x: i % rules.width, y: i / rules.width }, true);
+            }
+        }
+        self.matrix = Model::matrix_from_grid(solution, rules.width);
+        self.rules = rules;
+        self.reset();
+    }
+
+    /// Starts the current puzzle again.
+    pub fn restart(&mut self) {
+        self.reset();
+        self.push_started();
+    }
+
+    pub(crate) fn reset(&mut self) {
+        self.user_matrix = Default::default();
+        self.marked_coord = vec![];
+        self.no_valid = vec![];
+        self.notes = vec![0; self.width * self.width];
+        self.elapsed = 0.;
+        self.is_paused = false;
+        self.is_finish = false;
+        self.is_lost = false;
+        self.mistakes = 0;
+        self.score = 0;
+        self.hints_used = 0;
+        self.checks_used = 0;
+        self.scored = Default::default();
+        self.moves = vec![];
+    }
+
+    /// Whether the cell belongs to the puzzle, the corners between samurai grids don't.
+    pub fn is_active(&self, x: usize, y: usize) -> bool {
+        return self.rules.active[y * self.width + x];
+    }
+
+    pub fn is_given(&self, x: usize, y: usize) -> bool {
+        return self.is_active(x, y) && !self.empties.contains_key(&Key { x, y });
+    }
+
+    /// The board as the rules see it: givens and the user's digits, 0 for empty cells.
+    pub fn current_grid(&self) -> Vec<u8> {
+        let mut grid = vec![0; self.width * self.width];
+        for y in 0..self.width {
+            for x in 0..self.width {
+                let key = Key { x, y };
+                if !self.is_active(x, y) {
+                    continue;
+                }
+                if !self.empties.contains_key(&key) {
+                    grid[y * self.width + x] = self.matrix[y][x] + 1;
+                    continue;
+                }
+                match self.user_matrix.get(&key) {
+                    None => {}
+                    Some(v) => { grid[y * self.width + x] = *v }
+                }
+            }
+        }
+        return grid;
+    }
+
+    /// The board as one line of `notation`, givens and the user's digits.
+    pub fn get_board(&self) -> String {
+        return notation::to_line(&self.current_grid());
+    }
+
+    pub fn selected(&self) -> Option<[usize; 2]> {
+        return self.marked_coord.first().copied();
+    }
+
+    /// Candidates noted in the cell, bit n for digit n.
+    pub fn notes(&self, x: usize, y: usize) -> u32 {
+        return self.notes[y * self.width + x];
+    }
+
+    /// Goes back to the start, an unfinished game counts as abandoned.
+    pub fn leave(&mut self) {
+        if !self.is_finish && !self.is_lost {
+            self.events.push(Event::Abandoned { difficult: self.current_difficult, elapsed: self.elapsed });
+        }
+    }
+
+    /// Events since the previous call, oldest first.
+    pub fn take_events(&mut self) -> Vec<Event> {
+        return std::mem::take(&mut self.events);
+    }
+
+    fn push_started(&mut self) {
+        self.events.push(Event::Started { difficult: self.current_difficult, variant: self.current_variant, size: self.size });
+    }
+
+    pub(crate) fn get_empties(&self) -> Vec<[usize; 2]> {
+        let mut empties: Vec<[usize; 2]> = self.empties.keys().map(|k| [k.x, k.y]).collect();
+        empties.sort();
+        return empties;
+    }
+
+    /// Adds `delta` seconds of play. A gap longer than `MAX_FRAME_GAP` means
+    /// the game wasn't on screen (browsers stop calling frames for background
+    /// tabs, a terminal may be suspended), so the game is paused instead.
+    pub fn tick(&mut self, delta: f64) {
+        if self.is_paused || self.is_finish || self.is_lost {
+            return;
+        }
+        if delta > MAX_FRAME_GAP {
+            self.pause();
+        } else {
+            self.elapsed += delta;
+        }
+    }
+
+    pub fn pause(&mut self) {
+        if self.is_finish || self.is_lost {
+            return;
+        }
+        self.is_paused = true;
+        self.marked_coord = vec![];
+    }
+
+    pub fn resume(&mut self) {
+        self.is_paused = false;
+    }
+
+    pub fn get_duration(&self) -> String {
+        let seconds = self.elapsed as u64;
+        return format!("{:02}:{:02}", seconds / 60, seconds % 60);
+    }
+
+    pub fn is_win(&mut self) -> bool {
+        if self.is_finish {
+            return true;
+        }
+        for (key, _) in &self.empties {
+            match self.user_matrix.get(&key) {
+                None => { return false; }
+                Some(v) => {
+                    if self.matrix[key.y][key.x] != v.clone() - 1 {
+                        return false;
+                    }
+                    continue;
+                }
+            }
+        }
+        self.is_finish = true;
+        self.score += self.time_bonus();
+        self.add_record(true);
+        self.events.push(Event::Won {
+            difficult: self.current_difficult,
+            variant: self.current_variant,
+            chess: self.chess,
+            size: self.size,
+            seed: if self.is_custom { None } else { Some(self.seed) },
+            day: self.day,
+            elapsed: self.elapsed,
+            score: self.score,
+            mistakes: self.mistakes,
+            hints: self.hints_used,
+            moves: self.moves.clone(),
+        });
+        return true;
+    }
+
+    fn difficult_multiplier(&self) -> i64 {
+        match self.current_difficult {
+            Difficult::SuperEasy => 1,
+            Difficult::Easy => 2,
+            Difficult::Medium => 3,
+            Difficult::Hard => 4,
+        }
+    }
+
+    fn time_bonus(&self) -> i64 {
+        if self.elapsed >= TIME_BONUS_SECONDS {
+            return 0;
+        }
+        return (TIME_BONUS_SECONDS - self.elapsed) as i64 * self.difficult_multiplier() / 10;
+    }
+
+    fn add_penalty(&mut self, penalty: i64) {
+        self.score -= penalty;
+        if self.score < 0 {
+            self.score = 0;
+        }
+    }
+
+    fn add_record(&mut self, is_win: bool) {
+        self.stats.add(Record {
+            difficult: self.current_difficult,
+            variant: self.current_variant,
+            chess: self.chess,
+            size: self.size,
+            seed: if self.is_custom { None } else { Some(self.seed) },
+            rules: self.rules.clone(),
+            duration: self.elapsed,
+            score: self.score,
+            hints: self.hints_used,
+            checks: self.checks_used,
+            mistakes: self.mistakes,
+            is_win,
+            matrix: self.matrix.clone(),
+            empties: self.get_empties(),
+            moves: self.moves.clone(),
+        });
+    }
+
+    fn log(&mut self, action: Action) {
+        self.moves.push(Move { time: self.elapsed, action });
+    }
+
+    pub fn select(&mut self, x: usize, y: usize) {
+        if self.marked_coord.len() == 1 && self.marked_coord[0] == [x, y] {
+            return;
+        }
+        self.marked_coord = vec!([x, y]);
+        self.log(Action::Select { x, y });
+    }
+
+    pub fn unselect(&mut self) {
+        self.marked_coord = vec![];
+    }
+
+    pub fn erase_num(&mut self, x: usize, y: usize) {
+        let key = Key { x, y };
+        self.notes[y * self.width + x] = 0;
+        if self.is_finish || self.user_matrix.remove(&key).is_none() {
+            return;
+        }
+        self.log(Action::Erase { x, y });
+    }
+
+    /// Writes down or crosses out `num` as a candidate of an empty cell.
+    pub fn toggle_note(&mut self, x: usize, y: usize, num: u8) {
+        let key = Key { x, y };
+        if self.is_finish || !self.empties.contains_key(&key) || self.user_matrix.contains_key(&key) {
+            return;
+        }
+        self.notes[y * self.width + x] ^= 1 << num;
+        self.log(Action::Note { x, y, num });
+    }
+
+    pub fn get_score(&self) -> String {
+        return format!("Очки: {}", self.score);
+    }
+
+    pub fn get_best_score(&self) -> Option<String> {
+        self.stats.best_score(self.current_difficult).map(|best| format!("Рекорд: {}", best))
+    }
+
+    /// Puts `num` into an empty cell. With a mistake limit set, every new wrong
+    /// value counts as a mistake and the game is lost once the limit is reached.
+    pub fn place_num(&mut self, x: usize, y: usize, num: u8) {
+        let key = Key { x, y };
+        if self.is_lost || self.is_finish || !self.empties.contains_key(&key) {
+            return;
+        }
+        if self.user_matrix.get(&key) == Some(&num) {
+            return;
+        }
+        self.log(Action::Place { x, y, num });
+        self.clear_notes(x, y, num);
+        let is_mistake = self.matrix[y][x] != num - 1;
+        if !is_mistake {
+            if !self.scored.contains_key(&key) {
+                self.score += CELL_POINTS * self.difficult_multiplier();
+                self.scored.insert(Key { x, y }, true);
+            }
+            self.user_matrix.insert(key, num);
+            return;
+        }
+        self.user_matrix.insert(key, num);
+        self.add_penalty(MISTAKE_PENALTY);
+        match self.mistake_limit {
+            None => {}
+            Some(limit) => {
+                self.mistakes += 1;
+                if self.mistakes >= limit {
+                    self.is_lost = true;
+                    self.marked_coord = vec![];
+                    self.add_record(false);
+                    self.events.push(Event::Lost { difficult: self.current_difficult, elapsed: self.elapsed });
+                }
+            }
+        }
+    }
+
+    /// The notes of the cell and `num` in the notes of every cell it can't repeat in.
+    pub(crate) fn clear_notes(&mut self, x: usize, y: usize, num: u8) {
+        let cell = y * self.width + x;
+        self.notes[cell] = 0;
+        for unit in self.rules.units.iter().filter(|u| u.contains(&cell)) {
+            for &other in unit {
+                self.notes[other] &= !(1 << num);
+            }
+        }
+    }
+
+    pub fn switch_mistake_limit(&mut self) {
+        self.mistake_limit = match self.mistake_limit {
+            None => Some(3),
+            Some(3) => Some(5),
+            Some(_) => None,
+        };
+    }
+
+    pub fn get_mistakes(&self) -> Option<String> {
+        self.mistake_limit.map(|limit| format!("Ошибки: {}/{}", self.mistakes, limit))
+    }
+
+    /// Puts the right digit into the selected cell, or into the first empty
+    /// cell without it when the selected one is already right. The cell is
+    /// selected afterwards.
+    pub fn hint(&mut self) {
+        if self.is_lost || self.is_finish {
+            return;
+        }
+        let is_right = |model: &Model, x: usize, y: usize| {
+            model.user_matrix.get(&Key { x, y }) == Some(&(model.matrix[y][x] + 1))
+        };
+        let mut target = match self.selected() {
+            Some([x, y]) if self.empties.contains_key(&Key { x, y }) && !is_right(self, x, y) => Some([x, y]),
+            _ => None,
+        };
+        if target.is_none() {
+            let mut empties = self.get_empties();
+            // row by row, like a person reads the board
+            empties.sort_by_key(|[x, y]| (*y, *x));
+            target = empties.into_iter().find(|&[x, y]| !is_right(self, x, y));
+        }
+        let [x, y] = match target {
+            None => { return; }
+            Some(target) => target,
+        };
+        let num = self.matrix[y][x] + 1;
+        self.select(x, y);
+        self.hints_used += 1;
+        self.events.push(Event::HintUsed { hints: self.hints_used });
+        self.add_penalty(HINT_PENALTY);
+        self.scored.insert(Key { x, y }, true);
+        self.log(Action::Hint { x, y, num });
+        self.clear_notes(x, y, num);
+        self.user_matrix.insert(Key { x, y }, num);
+    }
+
+    /// Marks the wrong digits, every check costs points.
+    pub fn validate(&mut self) {
+        if self.is_lost || self.is_finish {
+            return;
+        }
+        self.checks_used += 1;
+        self.add_penalty(CHECK_PENALTY);
+        self.log(Action::Check);
+        self.find_no_valid();
+    }
+
+    pub(crate) fn find_no_valid(&mut self) {
+        self.no_valid = vec![];
+        for y in (0..self.width).step_by(1) {
+            for x in (0..self.width).step_by(1) {
+                let num = self.user_matrix.get(&Key { x, y });
+                match num {
+                    None => {
+                        continue;
+                    }
+                    Some(v) => {
+                        if self.matrix[y][x] == v.clone() - 1 {
+                            continue;
+                        }
+                        self.no_valid.push([x, y])
+                    }
+                }
+            }
+        }
+        for cell in self.rules.conflicts(&self.current_grid()) {
+            let coord = [cell % self.width, cell / self.width];
+            if !self.empties.contains_key(&Key { x: coord[0], y: coord[1] }) || self.no_valid.contains(&coord) {
+                continue;
+            }
+            self.no_valid.push(coord);
+        }
+    }
+
+    pub(crate) fn matrix_from_grid(grid: &[u8], width: usize) -> Vec<Vec<u8>> {
+        let mut data: Vec<Vec<u8>> = vec!();
+        for y in (0..width).step_by(1) {
+            let mut new_line: Vec<u8> = vec![0; width];
+            for x in (0..width).step_by(1) {
+                // cells outside of samurai grids stay 0 in the solution
+                new_line[x] = grid[x + (y * width)].saturating_sub(1)
+            }
+            data.push(new_line);
+        }
+        return data;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn model(difficult: Difficult) -> Model {
+        let mut model = Model::new(Variant::Classic, Chess::Off, 4, difficult, 7).unwrap();
+        model.take_events();
+        return model;
+    }
+
+    // an empty cell with its right digit and a wrong one
+    fn empty_cell(model: &Model) -> (usize, usize, u8, u8) {
+        let [x, y] = model.get_empties()[0];
+        let right = model.matrix[y][x] + 1;
+        return (x, y, right, right % 4 + 1);
+    }
+
+    fn solve(model: &mut Model) {
+        for [x, y] in model.get_empties() {
+            let num = model.matrix[y][x] + 1;
+            model.place_num(x, y, num);
+        }
+    }
+
+    #[test]
+    fn right_digit_scores_once_by_difficulty() {
+        let mut model = model(Difficult::Medium);
+        let (x, y, right, _) = empty_cell(&model);
+        model.place_num(x, y, right);
+        assert_eq!(model.score, CELL_POINTS * 3);
+        model.erase_num(x, y);
+        model.place_num(x, y, right);
+        assert_eq!(model.score, CELL_POINTS * 3);
+    }
+
+    #[test]
+    fn penalties_stop_at_zero() {
+        let mut model = model(Difficult::Hard);
+        let (x, y, right, wrong) = empty_cell(&model);
+        model.place_num(x, y, right);
+        model.erase_num(x, y);
+        model.place_num(x, y, wrong);
+        assert_eq!(model.score, CELL_POINTS * 4 - MISTAKE_PENALTY);
+        model.validate();
+        assert_eq!(model.score, 0);
+        model.hint();
+        assert_eq!(model.score, 0);
+    }
+
+    #[test]
+    fn time_bonus_is_a_tenth_of_a_point_per_second_left() {
+        let mut model = model(Difficult::Hard);
+        model.elapsed = 800.;
+        assert_eq!(model.time_bonus(), 1000 * 4 / 10);
+        model.elapsed = TIME_BONUS_SECONDS + 5.;
+        assert_eq!(model.time_bonus(), 0);
+
+        model.elapsed = 1700.;
+        solve(&mut model);
+        assert!(model.is_win());
+        let cells = model.get_empties().len() as i64;
+        assert_eq!(model.score, cells * CELL_POINTS * 4 + 100 * 4 / 10);
+    }
+
+    #[test]
+    fn mistakes_count_only_with_a_limit() {
+        let mut model = model(Difficult::Easy);
+        let (x, y, _, wrong) = empty_cell(&model);
+        model.place_num(x, y, wrong);
+        assert_eq!(model.mistakes, 0);
+        assert!(!model.is_lost);
+    }
+
+    #[test]
+    fn reaching_the_mistake_limit_loses() {
+        let mut model = model(Difficult::Easy);
+        model.mistake_limit = Some(3);
+        let (x, y, right, wrong) = empty_cell(&model);
+        let other = wrong % 4 + 1;
+        let other = if other == right { other % 4 + 1 } else { other };
+        // putting the same wrong digit again isn't a new mistake
+        model.place_num(x, y, wrong);
+        model.place_num(x, y, wrong);
+        model.place_num(x, y, other);
+        assert_eq!(model.mistakes, 2);
+        assert!(!model.is_lost);
+
+        model.place_num(x, y, wrong);
+        assert_eq!(model.mistakes, 3);
+        assert!(model.is_lost);
+        assert!(model.take_events().iter().any(|e| matches!(e, Event::Lost { .. })));
+        assert!(!model.stats.last().unwrap().is_win);
+
+        // nothing can be put after losing
+        model.place_num(x, y, right);
+        assert_eq!(model.user_matrix.get(&Key { x, y }), Some(&wrong));
+    }
+
+    #[test]
+    fn record_keeps_the_rules_for_the_replay() {
+        let mut model = Model::new(Variant::Jigsaw, Chess::Off, 9, Difficult::SuperEasy, 3).unwrap();
+        solve(&mut model);
+        assert!(model.is_win());
+        let record = model.stats.last().unwrap();
+        assert_eq!(record.variant, Variant::Jigsaw);
+        assert_eq!(record.seed, Some(3));
+        assert_eq!(record.rules.regions, model.rules.regions);
+    }
+
+    #[test]
+    fn notes_are_logged_and_read_back() {
+        let mut model = model(Difficult::Easy);
+        let (x, y, right, _) = empty_cell(&model);
+        model.toggle_note(x, y, right);
+        assert_eq!(model.notes(x, y), 1 << right);
+        let logged = *model.moves.last().unwrap();
+        assert_eq!(logged.action, Action::Note { x, y, num: right });
+
+        let read = Move::from_json(&crate::json::parse(&logged.to_json()).unwrap()).unwrap();
+        assert_eq!(read.action, logged.action);
+    }
+}
@@ -0,0 +1,33 @@
+/// Puzzles as one line of text, row by row: one symbol per cell
+/// ("123456789ABCDEFG") and "." for an empty cell, 81 characters for 9x9.
+const SYMBOLS: &str = "123456789ABCDEFG";
+
+pub fn to_line(grid: &[u8]) -> String {
+    return grid.iter()
+        .map(|&num| match num {
+            0 => '.',
+            n => SYMBOLS.chars().nth(n as usize - 1).unwrap(),
+        })
+        .collect();
+}
+
+/// Reads a line written by `to_line`, "0" also means an empty cell. The grid
+/// size comes from the length, None for an unsupported length or a symbol
+/// that doesn't fit the size.
+pub fn from_line(line: &str) -> Option<(usize, Vec<u8>)> {
+    let line = line.trim();
+    let cells = line.chars().count();
+    let size = *[4, 6, 9, 12, 16].iter().find(|&&s| s * s == cells)?;
+    let mut grid = vec![];
+    for c in line.chars() {
+        let num = match c {
+            '.' | '0' => 0,
+            c => SYMBOLS.chars().position(|s| s == c.to_ascii_uppercase())? as u8 + 1,
+        };
+        if num as usize > size {
+            return None;
+        }
+        grid.push(num);
+    }
+    return Some((size, grid));
+}
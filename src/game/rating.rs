@@ -0,0 +1,157 @@
+use crate::game::solver::{Rng, Rules};
+
+/// Ways a person finds the next step, from the easiest one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Technique {
+    NakedSingle,
+    HiddenSingle,
+    NakedPair,
+    // all places of a digit in one unit lie inside another unit
+    Intersection,
+    // nothing above works, the digit comes from the solution
+    Guess,
+}
+
+impl Technique {
+    pub fn name(&self) -> &str {
+        match self {
+            Technique::NakedSingle => "naked single",
+            Technique::HiddenSingle => "hidden single",
+            Technique::NakedPair => "naked pair",
+            Technique::Intersection => "intersection",
+            Technique::Guess => "guess",
+        }
+    }
+}
+
+/// A digit put into `cell`, or `removed` candidates crossed out.
+#[derive(Clone, Debug)]
+pub struct Step {
+    pub technique: Technique,
+    pub cell: Option<usize>,
+    pub num: u8,
+    pub removed: usize,
+}
+
+impl Step {
+    pub fn describe(&self, width: usize) -> String {
+        match self.cell {
+            Some(cell) => format!("{}: r{}c{} = {}", self.technique.name(), cell / width + 1, cell % width + 1, self.num),
+            None => format!("{}: {} candidates of {} removed", self.technique.name(), self.removed, self.num),
+        }
+    }
+}
+
+/// Solves `puzzle` the way a person would, trying the easiest technique
+/// first at every step. None when the puzzle has no solution.
+pub fn solve_path(rules: &Rules, puzzle: &[u8]) -> Option<Vec<Step>> {
+    if !rules.conflicts(puzzle).is_empty() {
+        return None;
+    }
+    let mut solution = puzzle.to_vec();
+    let mut budget = usize::MAX;
+    if !rules.fill(&mut solution, &mut Rng::new(0), &mut budget) {
+        return None;
+    }
+    let mut grid = puzzle.to_vec();
+    let mut masks: Vec<u32> = (0..rules.cells())
+        .map(|cell| if grid[cell] == 0 && rules.active[cell] { rules.candidates(&grid, cell) } else { 0 })
+        .collect();
+    let mut steps = vec![];
+    loop {
+        let step = match next_step(rules, &grid, &mut masks) {
+            Some(step) => step,
+            None => {
+                // the empty cell with the fewest candidates is the natural guess
+                let cell = (0..rules.cells())
+                    .filter(|&c| grid[c] == 0 && rules.active[c])
+                    .min_by_key(|&c| masks[c].count_ones());
+                match cell {
+                    None => { return Some(steps); }
+                    Some(cell) => Step { technique: Technique::Guess, cell: Some(cell), num: solution[cell], removed: 0 },
+                }
+            }
+        };
+        if let Some(cell) = step.cell {
+            grid[cell] = step.num;
+            masks[cell] = 0;
+            for (c, mask) in masks.iter_mut().enumerate() {
+                if *mask != 0 {
+                    *mask &= rules.candidates(&grid, c);
+                }
+            }
+        }
+        steps.push(step);
+    }
+}
+
+/// The hardest technique the puzzle needs, None when it has no solution.
+pub fn hardest(rules: &Rules, puzzle: &[u8]) -> Option<Technique> {
+    let steps = solve_path(rules, puzzle)?;
+    return Some(steps.iter().map(|s| s.technique).max().unwrap_or(Technique::NakedSingle));
+}
+
+fn next_step(rules: &Rules, grid: &[u8], masks: &mut [u32]) -> Option<Step> {
+    for (cell, mask) in masks.iter().enumerate() {
+        if mask.count_ones() == 1 {
+            let num = mask.trailing_zeros() as u8;
+            return Some(Step { technique: Technique::NakedSingle, cell: Some(cell), num, removed: 0 });
+        }
+    }
+    for unit in &rules.units {
+        for num in 1..=rules.size as u8 {
+            let places: Vec<usize> = unit.iter().cloned().filter(|&c| masks[c] & (1 << num) != 0).collect();
+            if places.len() == 1 && !unit.iter().any(|&c| grid[c] == num) {
+                return Some(Step { technique: Technique::HiddenSingle, cell: Some(places[0]), num, removed: 0 });
+            }
+        }
+    }
+    for unit in &rules.units {
+        for (i, &a) in unit.iter().enumerate() {
+            if masks[a].count_ones() != 2 {
+                continue;
+            }
+            for &b in &unit[i + 1..] {
+                if masks[b] != masks[a] {
+                    continue;
+                }
+                let pair = masks[a];
+                let mut removed = 0;
+                for &c in unit {
+                    if c != a && c != b && masks[c] & pair != 0 {
+                        removed += (masks[c] & pair).count_ones() as usize;
+                        masks[c] &= !pair;
+                    }
+                }
+                if removed > 0 {
+                    let num = pair.trailing_zeros() as u8;
+                    return Some(Step { technique: Technique::NakedPair, cell: None, num, removed });
+                }
+            }
+        }
+    }
+    for unit in &rules.units {
+        for num in 1..=rules.size as u8 {
+            let places: Vec<usize> = unit.iter().cloned().filter(|&c| masks[c] & (1 << num) != 0).collect();
+            if places.len() < 2 {
+                continue;
+            }
+            for other in &rules.units {
+                if other == unit || !places.iter().all(|c| other.contains(c)) {
+                    continue;
+                }
+                let mut removed = 0;
+                for &c in other {
+                    if !unit.contains(&c) && masks[c] & (1 << num) != 0 {
+                        masks[c] &= !(1 << num);
+                        removed += 1;
+                    }
+                }
+                if removed > 0 {
+                    return Some(Step { technique: Technique::Intersection, cell: None, num, removed });
+                }
+            }
+        }
+    }
+    return None;
+}
@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+
+use crate::json::Json;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Action {
+    Select { x: usize, y: usize },
+    Place { x: usize, y: usize, num: u8 },
+    Erase { x: usize, y: usize },
+    Hint { x: usize, y: usize, num: u8 },
+    Check,
+    // writes down or crosses out a candidate
+    Note { x: usize, y: usize, num: u8 },
+}
+
+/// One player action, `time` is the game time (pauses excluded) it happened at.
+#[derive(Clone, Copy, Debug)]
+pub struct Move {
+    pub time: f64,
+    pub action: Action,
+}
+
+impl Move {
+    /// {"t":12.345,"a":"place","x":1,"y":2,"n":5}, "x", "y" and "n" only
+    /// where the action has them.
+    pub fn to_json(&self) -> String {
+        let mut fields = vec![format!("\"t\":{:.3}", self.time)];
+        match self.action {
+            Action::Select { x, y } => {
+                fields.push("\"a\":\"select\"".to_string());
+                fields.push(format!("\"x\":{},\"y\":{}", x, y));
+            }
+            Action::Place { x, y, num } => {
+                fields.push("\"a\":\"place\"".to_string());
+                fields.push(format!("\"x\":{},\"y\":{},\"n\":{}", x, y, num));
+            }
+            Action::Erase { x, y } => {
+                fields.push("\"a\":\"erase\"".to_string());
+                fields.push(format!("\"x\":{},\"y\":{}", x, y));
+            }
+            Action::Hint { x, y, num } => {
+                fields.push("\"a\":\"hint\"".to_string());
+                fields.push(format!("\"x\":{},\"y\":{},\"n\":{}", x, y, num));
+            }
+            Action::Check => fields.push("\"a\":\"check\"".to_string()),
+            Action::Note { x, y, num } => {
+                fields.push("\"a\":\"note\"".to_string());
+                fields.push(format!("\"x\":{},\"y\":{},\"n\":{}", x, y, num));
+            }
+        }
+        return format!("{{{}}}", fields.join(","));
+    }
+
+    pub fn from_json(json: &Json) -> Option<Move> {
+        let number = |key: &str| -> Option<usize> {
+            let n = json.get(key)?.as_f64()?;
+            if n < 0. || n.fract() != 0. || n > 255. {
+                return None;
+            }
+            return Some(n as usize);
+        };
+        let action = match json.get("a")?.as_str()? {
+            "select" => Action::Select { x: number("x")?, y: number("y")? },
+            "place" => Action::Place { x: number("x")?, y: number("y")?, num: number("n")? as u8 },
+            "erase" => Action::Erase { x: number("x")?, y: number("y")? },
+            "hint" => Action::Hint { x: number("x")?, y: number("y")?, num: number("n")? as u8 },
+            "check" => Action::Check,
+            "note" => Action::Note { x: number("x")?, y: number("y")?, num: number("n")? as u8 },
+            _ => { return None; }
+        };
+        return Some(Move { time: json.get("t")?.as_f64()?, action });
+    }
+}
+
+const SPEEDS: [f64; 4] = [1., 2., 4., 8.];
+
+pub struct Replay {
+    pub moves: Vec<Move>,
+    pub duration: f64,
+    pub position: f64,
+    pub is_playing: bool,
+    speed_index: usize,
+}
+
+impl Replay {
+    pub fn new(moves: Vec<Move>, duration: f64) -> Self {
+        return Replay {
+            moves,
+            duration,
+            position: 0.,
+            is_playing: true,
+            speed_index: 0,
+        };
+    }
+
+    pub fn advance(&mut self, delta: f64) {
+        if !self.is_playing {
+            return;
+        }
+        self.position += delta * self.speed();
+        if self.position >= self.duration {
+            self.position = self.duration;
+            self.is_playing = false;
+        }
+    }
+
+    pub fn play_or_stop(&mut self) {
+        if self.is_playing {
+            self.is_playing = false;
+            return;
+        }
+        if self.position >= self.duration {
+            self.position = 0.;
+        }
+        self.is_playing = true;
+    }
+
+    pub fn speed(&self) -> f64 {
+        return SPEEDS[self.speed_index];
+    }
+
+    pub fn switch_speed(&mut self) {
+        self.speed_index = (self.speed_index + 1) % SPEEDS.len();
+    }
+
+    /// Moves the playback to `fraction` (0..1) of the whole solve.
+    pub fn seek(&mut self, fraction: f64) {
+        self.position = self.duration * fraction.clamp(0., 1.);
+    }
+
+    pub fn progress(&self) -> f64 {
+        if self.duration <= 0. {
+            return 1.;
+        }
+        return self.position / self.duration;
+    }
+
+    pub fn visible_moves(&self) -> Vec<Move> {
+        return self.moves.iter().filter(|m| m.time <= self.position).cloned().collect();
+    }
+}
+
+/// A previous solve of the same puzzle the player races against.
+pub struct Ghost {
+    pub duration: f64,
+    // (time, x, y, whether the ghost's value in the cell is correct after the move)
+    events: Vec<(f64, usize, usize, bool)>,
+    // times[n] is the moment the ghost had n + 1 correct cells for the first time
+    times: Vec<f64>,
+}
+
+impl Ghost {
+    pub fn new(moves: &[Move], duration: f64, matrix: &[Vec<u8>]) -> Self {
+        let mut board: HashMap<[usize; 2], bool> = HashMap::new();
+        let mut events = vec![];
+        let mut times = vec![];
+        for m in moves {
+            let (x, y, is_correct) = match m.action {
+                Action::Place { x, y, num } => (x, y, matrix[y][x] == num - 1),
+                Action::Hint { x, y, num } => (x, y, matrix[y][x] == num - 1),
+                Action::Erase { x, y } => (x, y, false),
+                _ => { continue; }
+            };
+            board.insert([x, y], is_correct);
+            events.push((m.time, x, y, is_correct));
+            let count = board.values().filter(|v| **v).count();
+            if count > times.len() {
+                times.push(m.time);
+            }
+        }
+        return Ghost { duration, events, times };
+    }
+
+    /// Cells the ghost had filled correctly at `time`.
+    pub fn cells_at(&self, time: f64) -> Vec<[usize; 2]> {
+        let mut board: HashMap<[usize; 2], bool> = HashMap::new();
+        for (t, x, y, is_correct) in &self.events {
+            if *t > time {
+                break;
+            }
+            board.insert([*x, *y], *is_correct);
+        }
+        return board.iter().filter(|(_, v)| **v).map(|(k, _)| *k).collect();
+    }
+
+    /// How far the player is behind (positive) or ahead (negative) of the ghost,
+    /// comparing the moments both reached `correct` filled cells.
+    pub fn delta(&self, correct: usize, time: f64, is_finish: bool) -> Option<f64> {
+        if is_finish {
+            return Some(time - self.duration);
+        }
+        if correct == 0 || self.times.is_empty() {
+            return None;
+        }
+        let index = correct.min(self.times.len()) - 1;
+        return Some(time - self.times[index]);
+    }
+}
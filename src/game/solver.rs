@@ -0,0 +1,679 @@
+use crate::game::constraints::{Cage, Constraint, Pair, Relation};
+
+// Some jigsaw layouts have no solution at all, so filling gives up at some point.
+const FILL_BUDGET: usize = 20_000;
+const FILL_ATTEMPTS: usize = 5;
+// Guesses allowed when checking that a puzzle is still unique, matters for 12x12 and 16x16.
+// Propagation settles most of the grid between guesses, so a few hundred are enough.
+const CHECK_BUDGET: usize = 300;
+// Guesses allowed for a puzzle that comes from the page, a sensible one needs far fewer.
+pub const LOAD_BUDGET: usize = 2_000;
+pub const KNIGHT_MOVES: [(i32, i32); 8] = [(1, 2), (2, 1), (2, -1), (1, -2), (-1, -2), (-2, -1), (-2, 1), (-1, 2)];
+pub const KING_MOVES: [(i32, i32); 8] = [(1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1), (0, -1), (1, -1)];
+// Top left corners of the five samurai grids.
+const SAMURAI_GRIDS: [(usize, usize); 5] = [(0, 0), (12, 0), (6, 6), (0, 12), (12, 12)];
+
+/// Grid cells are stored row by row, 0 is an empty cell and 1..=size are digits.
+/// The board is `width` cells across, only samurai makes it wider than `size`
+/// and leaves cells that belong to no grid (`active` is false, always 0).
+#[derive(Clone)]
+pub struct Rules {
+    pub size: usize,
+    pub width: usize,
+    pub active: Vec<bool>,
+    pub units: Vec<Vec<usize>>,
+    pub constraints: Vec<Constraint>,
+    // cells a chess move apart that can't hold the same digit, smaller cell first
+    pub chess: Vec<[usize; 2]>,
+    // region (box) number of every cell
+    pub regions: Vec<usize>,
+    peers: Vec<Vec<usize>>,
+    // constraints every cell takes part in
+    cell_constraints: Vec<Vec<usize>>,
+}
+
+impl Rules {
+    pub fn classic(size: usize) -> Self {
+        return Rules::jigsaw(size, Rules::box_regions(size));
+    }
+
+    /// Sudoku X: both main diagonals are units as well.
+    pub fn diagonal(size: usize) -> Self {
+        let regions = Rules::box_regions(size);
+        let mut units = Rules::region_units(size, &regions);
+        units.push((0..size).map(|i| i * size + i).collect());
+        units.push((0..size).map(|i| i * size + size - 1 - i).collect());
+        return Rules::from_units(size, size, units, regions);
+    }
+
+    /// Windoku: four more 3x3 boxes, rows and columns 1-3 and 5-7, are units too.
+    pub fn windoku() -> Self {
+        let regions = Rules::box_regions(9);
+        let mut units = Rules::region_units(9, &regions);
+        for (ox, oy) in [(1, 1), (5, 1), (1, 5), (5, 5)] {
+            units.push((0..9).map(|c| (oy + c / 3) * 9 + ox + c % 3).collect());
+        }
+        return Rules::from_units(9, 9, units, regions);
+    }
+
+    /// Rows, columns and the given regions instead of boxes.
+    pub fn jigsaw(size: usize, regions: Vec<usize>) -> Self {
+        return Rules::from_units(size, size, Rules::region_units(size, &regions), regions);
+    }
+
+    /// Killer sudoku: classic units plus cages with sums.
+    pub fn killer(size: usize, cages: Vec<Cage>) -> Self {
+        let mut rules = Rules::classic(size);
+        for cage in cages {
+            for &cell in &cage.cells {
+                for &other in &cage.cells {
+                    if other != cell && !rules.peers[cell].contains(&other) {
+                        rules.peers[cell].push(other);
+                    }
+                }
+            }
+            rules.add_constraint(Constraint::Cage(cage));
+        }
+        return rules;
+    }
+
+    /// Samurai: five 9x9 grids on a 21x21 board, the middle one shares a corner
+    /// box with each of the others. Every grid keeps its own rows and columns,
+    /// so shared cells have to fit all grids they belong to.
+    pub fn samurai() -> Self {
+        let width = 21;
+        let mut units: Vec<Vec<usize>> = vec![];
+        for (ox, oy) in SAMURAI_GRIDS {
+            for i in 0..9 {
+                units.push((0..9).map(|x| (oy + i) * width + ox + x).collect());
+                units.push((0..9).map(|y| (oy + y) * width + ox + i).collect());
+                let (bx, by) = (ox + i % 3 * 3, oy + i / 3 * 3);
+                let mut unit: Vec<usize> = (0..9).map(|c| (by + c / 3) * width + bx + c % 3).collect();
+                unit.sort();
+                // corner boxes of the middle grid are boxes of an outer grid too
+                if !units.contains(&unit) {
+                    units.push(unit);
+                }
+            }
+        }
+        let mut regions = vec![usize::MAX; width * width];
+        for unit in units.iter().filter(|u| u[8] - u[0] == width * 2 + 2) {
+            for &cell in unit {
+                regions[cell] = cell / width / 3 * 7 + cell % width / 3;
+            }
+        }
+        return Rules::from_units(9, width, units, regions);
+    }
+
+    /// Classic units plus relations between neighbouring cells.
+    pub fn pairwise(size: usize, pairs: Vec<Pair>) -> Self {
+        let mut rules = Rules::classic(size);
+        for pair in pairs {
+            rules.add_constraint(Constraint::Pair(pair));
+        }
+        return rules;
+    }
+
+    fn add_constraint(&mut self, constraint: Constraint) {
+        for cell in constraint.cells() {
+            self.cell_constraints[cell].push(self.constraints.len());
+        }
+        self.constraints.push(constraint);
+    }
+
+    /// Forbids the same digit in cells one of `moves` apart, knight or king
+    /// moves for the chess variants.
+    pub fn add_moves(&mut self, moves: &[(i32, i32)]) {
+        let width = self.width as i32;
+        for cell in 0..self.cells() {
+            let (x, y) = ((cell % self.width) as i32, (cell / self.width) as i32);
+            for (dx, dy) in moves {
+                let (nx, ny) = (x + dx, y + dy);
+                if nx < 0 || ny < 0 || nx >= width || ny >= width {
+                    continue;
+                }
+                let other = (ny * width + nx) as usize;
+                if other < cell || self.chess.contains(&[cell, other]) {
+                    continue;
+                }
+                self.chess.push([cell, other]);
+                if !self.peers[cell].contains(&other) {
+                    self.peers[cell].push(other);
+                    self.peers[other].push(cell);
+                }
+            }
+        }
+    }
+
+    /// Greater-than signs between all neighbours inside the same box,
+    /// `a` of every pair is the bigger digit of the solution.
+    pub fn make_inequalities(size: usize, solution: &[u8]) -> Vec<Pair> {
+        let regions = Rules::box_regions(size);
+        let mut pairs = vec![];
+        for cell in 0..size * size {
+            for n in Rules::neighbours(size, cell) {
+                if n < cell || regions[n] != regions[cell] {
+                    continue;
+                }
+                let (a, b) = if solution[cell] > solution[n] { (cell, n) } else { (n, cell) };
+                pairs.push(Pair { a, b, relation: Relation::Greater });
+            }
+        }
+        return pairs;
+    }
+
+    /// Kropki dots between all neighbours of the solution, including the
+    /// neighbours without a dot.
+    pub fn make_kropki(size: usize, solution: &[u8]) -> Vec<Pair> {
+        let mut pairs = vec![];
+        for cell in 0..size * size {
+            for n in Rules::neighbours(size, cell) {
+                if n < cell {
+                    continue;
+                }
+                let relation = [Relation::Consecutive, Relation::Double, Relation::NoDot]
+                    .iter()
+                    .copied()
+                    .find(|&relation| Pair { a: cell, b: n, relation }.allows(solution[cell], solution[n]))
+                    .unwrap();
+                pairs.push(Pair { a: cell, b: n, relation });
+            }
+        }
+        return pairs;
+    }
+
+    /// Splits a solved grid into random cages of orthogonally connected cells
+    /// with different digits, mostly 2 to 4 cells each.
+    pub fn make_cages(size: usize, solution: &[u8], rng: &mut Rng) -> Vec<Cage> {
+        let mut cage_of: Vec<Option<usize>> = vec![None; size * size];
+        let mut cages: Vec<Cage> = vec![];
+        let mut order: Vec<usize> = (0..size * size).collect();
+        rng.shuffle(&mut order);
+        for start in order {
+            if cage_of[start].is_some() {
+                continue;
+            }
+            let target = 2 + rng.range(3);
+            let mut cells = vec![start];
+            cage_of[start] = Some(cages.len());
+            while cells.len() < target {
+                let mut next: Vec<usize> = vec![];
+                for &cell in &cells {
+                    for n in Rules::neighbours(size, cell) {
+                        if cage_of[n].is_none() && !next.contains(&n) && !cells.iter().any(|&c| solution[c] == solution[n]) {
+                            next.push(n);
+                        }
+                    }
+                }
+                if next.is_empty() {
+                    break;
+                }
+                let n = next[rng.range(next.len())];
+                cage_of[n] = Some(cages.len());
+                cells.push(n);
+            }
+            cells.sort();
+            let sum = cells.iter().map(|&c| solution[c] as u32).sum();
+            cages.push(Cage { cells, sum });
+        }
+        return cages;
+    }
+
+    /// Width and height of a box for the supported grid sizes.
+    pub fn box_size(size: usize) -> (usize, usize) {
+        match size {
+            4 => (2, 2),
+            6 => (3, 2),
+            12 => (4, 3),
+            16 => (4, 4),
+            _ => (3, 3),
+        }
+    }
+
+    fn box_regions(size: usize) -> Vec<usize> {
+        let (width, height) = Rules::box_size(size);
+        return (0..size * size)
+            .map(|cell| cell / size / height * (size / width) + cell % size / width)
+            .collect();
+    }
+
+    fn region_units(size: usize, regions: &[usize]) -> Vec<Vec<usize>> {
+        let mut units: Vec<Vec<usize>> = vec![];
+        for y in 0..size {
+            units.push((0..size).map(|x| y * size + x).collect());
+        }
+        for x in 0..size {
+            units.push((0..size).map(|y| y * size + x).collect());
+        }
+        for r in 0..size {
+            units.push((0..size * size).filter(|&cell| regions[cell] == r).collect());
+        }
+        return units;
+    }
+
+    /// Random jigsaw layout: starts from 3x3 boxes and keeps swapping cells
+    /// between neighbouring regions while both stay connected.
+    pub fn make_regions(size: usize, rng: &mut Rng) -> Vec<usize> {
+        let cells = size * size;
+        let mut regions = Rules::box_regions(size);
+        for _ in 0..cells * 25 {
+            let cell = rng.range(cells);
+            let around = Rules::neighbours(size, cell);
+            let other = around[rng.range(around.len())];
+            let (a, b) = (regions[cell], regions[other]);
+            if a == b {
+                continue;
+            }
+            let back: Vec<usize> = (0..cells)
+                .filter(|&c| regions[c] == b && c != other && Rules::neighbours(size, c).iter().any(|&n| regions[n] == a && n != cell))
+                .collect();
+            if back.is_empty() {
+                continue;
+            }
+            let swap = back[rng.range(back.len())];
+            regions[cell] = b;
+            regions[swap] = a;
+            if !Rules::is_connected(size, &regions, a) || !Rules::is_connected(size, &regions, b) {
+                regions[cell] = a;
+                regions[swap] = b;
+            }
+        }
+        return regions;
+    }
+
+    fn neighbours(size: usize, cell: usize) -> Vec<usize> {
+        let (x, y) = (cell % size, cell / size);
+        let mut around = vec![];
+        if x > 0 { around.push(cell - 1) }
+        if x + 1 < size { around.push(cell + 1) }
+        if y > 0 { around.push(cell - size) }
+        if y + 1 < size { around.push(cell + size) }
+        return around;
+    }
+
+    fn is_connected(size: usize, regions: &[usize], region: usize) -> bool {
+        let cells: Vec<usize> = (0..size * size).filter(|&c| regions[c] == region).collect();
+        let mut seen = vec![cells[0]];
+        let mut i = 0;
+        while i < seen.len() {
+            for n in Rules::neighbours(size, seen[i]) {
+                if regions[n] == region && !seen.contains(&n) {
+                    seen.push(n);
+                }
+            }
+            i += 1;
+        }
+        return seen.len() == cells.len();
+    }
+
+    fn from_units(size: usize, width: usize, units: Vec<Vec<usize>>, regions: Vec<usize>) -> Self {
+        let mut peers: Vec<Vec<usize>> = vec![vec![]; width * width];
+        let mut active = vec![false; width * width];
+        for unit in &units {
+            for &cell in unit {
+                active[cell] = true;
+                for &other in unit {
+                    if other != cell && !peers[cell].contains(&other) {
+                        peers[cell].push(other);
+                    }
+                }
+            }
+        }
+        return Rules {
+            size,
+            width,
+            active,
+            units,
+            constraints: vec![],
+            chess: vec![],
+            regions,
+            peers,
+            cell_constraints: vec![vec![]; width * width],
+        };
+    }
+
+    pub fn cells(&self) -> usize {
+        return self.width * self.width;
+    }
+
+    /// Digits `cell` can take, bit n set for digit n.
+    pub fn candidates(&self, grid: &[u8], cell: usize) -> u32 {
+        let mut mask: u32 = ((1 << self.size) - 1) << 1;
+        for &peer in &self.peers[cell] {
+            mask &= !(1 << grid[peer]);
+        }
+        for &i in &self.cell_constraints[cell] {
+            for num in 1..=self.size as u8 {
+                if mask & (1 << num) != 0 && !self.constraints[i].allows(grid, self.size, cell, num) {
+                    mask &= !(1 << num);
+                }
+            }
+        }
+        return mask;
+    }
+
+    /// Filled cells that share a unit with the same digit or break a constraint.
+    pub fn conflicts(&self, grid: &[u8]) -> Vec<usize> {
+        let mut cells = vec![];
+        for cell in 0..self.cells() {
+            if grid[cell] == 0 {
+                continue;
+            }
+            if self.peers[cell].iter().any(|&peer| grid[peer] == grid[cell]) {
+                cells.push(cell);
+            }
+        }
+        for constraint in &self.constraints {
+            for cell in constraint.broken(grid) {
+                if !cells.contains(&cell) {
+                    cells.push(cell);
+                }
+            }
+        }
+        return cells;
+    }
+
+    /// Pairs of cells a chess move apart holding the same digit.
+    pub fn chess_conflicts(&self, grid: &[u8]) -> Vec<[usize; 2]> {
+        return self.chess.iter()
+            .filter(|[a, b]| grid[*a] != 0 && grid[*a] == grid[*b])
+            .cloned()
+            .collect();
+    }
+
+    /// Empty cell with the fewest candidates, None when the grid is full.
+    fn best_cell(&self, grid: &[u8]) -> Option<(usize, u32)> {
+        let mut best: Option<(usize, u32)> = None;
+        for cell in 0..self.cells() {
+            if grid[cell] != 0 || !self.active[cell] {
+                continue;
+            }
+            let mask = self.candidates(grid, cell);
+            match best {
+                Some((_, m)) if m.count_ones() <= mask.count_ones() => {}
+                _ => { best = Some((cell, mask)) }
+            }
+            if mask.count_ones() <= 1 {
+                break;
+            }
+        }
+        return best;
+    }
+
+    /// Fills in everything the grid forces: cells with a single candidate and
+    /// digits with a single place left in a unit. False when some cell or
+    /// digit has no place at all.
+    fn propagate(&self, grid: &mut [u8]) -> bool {
+        loop {
+            // candidates are taken once per pass, digits put in during the pass
+            // only remove candidates, so every forced digit is checked again
+            let masks: Vec<u32> = (0..self.cells())
+                .map(|cell| if grid[cell] == 0 && self.active[cell] { self.candidates(grid, cell) } else { 0 })
+                .collect();
+            let mut changed = false;
+            for cell in 0..self.cells() {
+                if grid[cell] != 0 || !self.active[cell] {
+                    continue;
+                }
+                match masks[cell].count_ones() {
+                    0 => { return false; }
+                    1 => {
+                        if !self.force(grid, cell, masks[cell].trailing_zeros() as u8) {
+                            return false;
+                        }
+                        changed = true;
+                    }
+                    _ => {}
+                }
+            }
+            for unit in &self.units {
+                let mut present: u32 = 0;
+                for &cell in unit {
+                    present |= 1 << grid[cell];
+                }
+                for num in 1..=self.size as u8 {
+                    if present & (1 << num) != 0 {
+                        continue;
+                    }
+                    let mut places = unit.iter().filter(|&&cell| grid[cell] == 0 && masks[cell] & (1 << num) != 0);
+                    let cell = match (places.next(), places.next()) {
+                        (None, _) => { return false; }
+                        (Some(&cell), None) => cell,
+                        _ => { continue; }
+                    };
+                    if !self.force(grid, cell, num) {
+                        return false;
+                    }
+                    changed = true;
+                }
+            }
+            if !changed {
+                return true;
+            }
+        }
+    }
+
+    /// Puts a forced digit into the grid, false if the cell can't take it anymore.
+    fn force(&self, grid: &mut [u8], cell: usize, num: u8) -> bool {
+        if self.candidates(grid, cell) & (1 << num) == 0 {
+            return false;
+        }
+        grid[cell] = num;
+        return true;
+    }
+
+    /// Counts solutions of `grid`, stopping as soon as `limit` is reached.
+    pub fn count_solutions(&self, grid: &[u8], limit: usize) -> usize {
+        let mut budget = usize::MAX;
+        return self.count(grid, limit, &mut budget);
+    }
+
+    /// Like `count_solutions`, None when `budget` guesses weren't enough to tell.
+    pub fn try_count_solutions(&self, grid: &[u8], limit: usize, mut budget: usize) -> Option<usize> {
+        let count = self.count(grid, limit, &mut budget);
+        if budget == 0 {
+            return None;
+        }
+        return Some(count);
+    }
+
+    /// Same as `count_solutions`, but when `budget` guesses run out the grid
+    /// is reported as having `limit` solutions.
+    fn count(&self, grid: &[u8], limit: usize, budget: &mut usize) -> usize {
+        let mut grid = grid.to_vec();
+        if !self.propagate(&mut grid) {
+            return 0;
+        }
+        let (cell, mask) = match self.best_cell(&grid) {
+            None => { return 1; }
+            Some(best) => best,
+        };
+        let mut count = 0;
+        for num in 1..=self.size as u8 {
+            if mask & (1 << num) == 0 {
+                continue;
+            }
+            if *budget == 0 {
+                count = limit;
+                break;
+            }
+            *budget -= 1;
+            grid[cell] = num;
+            count += self.count(&grid, limit - count, budget);
+            if count >= limit {
+                break;
+            }
+        }
+        return count;
+    }
+
+    /// Fills `grid` with the first solution found trying digits in random order,
+    /// giving up after `budget` guesses.
+    pub fn fill(&self, grid: &mut Vec<u8>, rng: &mut Rng, budget: &mut usize) -> bool {
+        let mut work = grid.clone();
+        if !self.propagate(&mut work) {
+            return false;
+        }
+        let (cell, mask) = match self.best_cell(&work) {
+            None => {
+                *grid = work;
+                return true;
+            }
+            Some(best) => best,
+        };
+        let mut nums: Vec<u8> = (1..=self.size as u8).filter(|n| mask & (1 << n) != 0).collect();
+        rng.shuffle(&mut nums);
+        for num in nums {
+            if *budget == 0 {
+                break;
+            }
+            *budget -= 1;
+            work[cell] = num;
+            let mut next = work.clone();
+            if self.fill(&mut next, rng, budget) {
+                *grid = next;
+                return true;
+            }
+        }
+        return false;
+    }
+
+    /// Random solved grid. Random filling sometimes runs into a very long
+    /// search, so it starts over a few times before giving up.
+    pub fn generate_solved(&self, rng: &mut Rng) -> Option<Vec<u8>> {
+        for _ in 0..FILL_ATTEMPTS {
+            let mut grid = vec![0; self.cells()];
+            let mut budget = FILL_BUDGET;
+            if self.fill(&mut grid, rng, &mut budget) {
+                return Some(grid);
+            }
+        }
+        return None;
+    }
+
+    /// Removes digits from `solution` in random order while the puzzle stays
+    /// uniquely solvable, until only `givens` digits are left or nothing more
+    /// can be removed. Digits whose removal takes too long to check are kept.
+    pub fn make_puzzle(&self, solution: &[u8], givens: usize, rng: &mut Rng) -> Vec<u8> {
+        let mut puzzle = solution.to_vec();
+        let mut cells: Vec<usize> = (0..self.cells()).filter(|&c| self.active[c]).collect();
+        rng.shuffle(&mut cells);
+        let mut left = cells.len();
+        for cell in cells {
+            if left <= givens {
+                break;
+            }
+            let num = puzzle[cell];
+            puzzle[cell] = 0;
+            let mut budget = CHECK_BUDGET;
+            if self.count(&puzzle, 2, &mut budget) != 1 {
+                puzzle[cell] = num;
+                continue;
+            }
+            left -= 1;
+        }
+        return puzzle;
+    }
+}
+
+/// Small xorshift generator, so a puzzle can be rebuilt from its seed.
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    /// Scrambles the seed first (splitmix64), so neighbouring seeds
+    /// give unrelated sequences.
+    pub fn new(seed: u64) -> Self {
+        let mut z = seed.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        // xorshift never leaves the zero state
+        return Rng { state: if z == 0 { 1 } else { z } };
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        return self.state;
+    }
+
+    pub fn range(&mut self, max: usize) -> usize {
+        return (self.next_u64() % max as u64) as usize;
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = self.range(i + 1);
+            items.swap(i, j);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOLUTION: [u8; 16] = [
+        1, 2, 3, 4,
+        3, 4, 1, 2,
+        2, 1, 4, 3,
+        4, 3, 2, 1,
+    ];
+
+    #[test]
+    fn unique_puzzle_has_one_solution() {
+        let rules = Rules::classic(4);
+        let mut grid = SOLUTION.to_vec();
+        for &cell in &[0, 5, 10, 15, 3, 12] {
+            grid[cell] = 0;
+        }
+        assert_eq!(rules.count_solutions(&grid, 2), 1);
+        assert_eq!(rules.count_solutions(&SOLUTION, 2), 1);
+    }
+
+    #[test]
+    fn counting_stops_at_the_limit() {
+        let rules = Rules::classic(4);
+        let empty = vec![0; 16];
+        assert_eq!(rules.count_solutions(&empty, 2), 2);
+        assert_eq!(rules.count_solutions(&empty, 5), 5);
+        assert_eq!(rules.try_count_solutions(&empty, 100, 3), None);
+    }
+
+    #[test]
+    fn stuck_grid_has_no_solutions() {
+        let rules = Rules::classic(4);
+        let mut grid = vec![0; 16];
+        // the third cell of the first row can only be 3 or 4, both are in its column
+        grid[0] = 1;
+        grid[1] = 2;
+        grid[6] = 3;
+        grid[14] = 4;
+        assert_eq!(rules.conflicts(&grid), Vec::<usize>::new());
+        assert_eq!(rules.count_solutions(&grid, 2), 0);
+    }
+
+    #[test]
+    fn conflicts_mark_both_cells() {
+        let rules = Rules::classic(4);
+        assert!(rules.conflicts(&SOLUTION).is_empty());
+        let mut grid = SOLUTION.to_vec();
+        // the row, the column and the box of cell 5 now have two 2s
+        grid[5] = 2;
+        let mut conflicts = rules.conflicts(&grid);
+        conflicts.sort_unstable();
+        assert_eq!(conflicts, vec![1, 5, 7]);
+    }
+
+    #[test]
+    fn conflicts_include_broken_constraints() {
+        let cage = Cage { cells: vec![0, 1], sum: 4 };
+        let rules = Rules::killer(4, vec![cage]);
+        let mut conflicts = rules.conflicts(&SOLUTION);
+        conflicts.sort_unstable();
+        assert_eq!(conflicts, vec![0, 1]);
+    }
+}
@@ -0,0 +1,70 @@
+use crate::game::generator::{Chess, Difficult, Variant};
+use crate::game::replay::Move;
+use crate::game::solver::Rules;
+
+#[derive(Clone)]
+pub struct Record {
+    pub difficult: Difficult,
+    pub variant: Variant,
+    pub chess: Chess,
+    pub size: usize,
+    // None for a puzzle from the editor or the page
+    pub seed: Option<u64>,
+    // kept so a replay draws the same regions, cages and marks
+    pub rules: Rules,
+    pub duration: f64,
+    pub score: i64,
+    pub hints: u32,
+    pub checks: u32,
+    pub mistakes: u8,
+    pub is_win: bool,
+    pub matrix: Vec<Vec<u8>>,
+    pub empties: Vec<[usize; 2]>,
+    pub moves: Vec<Move>,
+}
+
+pub struct Statistics {
+    pub records: Vec<Record>,
+}
+
+impl Default for Statistics {
+    fn default() -> Self {
+        return Statistics::new();
+    }
+}
+
+impl Statistics {
+    pub fn new() -> Self {
+        return Statistics { records: vec![] };
+    }
+
+    pub fn add(&mut self, record: Record) {
+        self.records.push(record);
+    }
+
+    pub fn last(&self) -> Option<&Record> {
+        return self.records.last();
+    }
+
+    /// The fastest win on exactly this puzzle, used as the ghost to race.
+    pub fn best_for_puzzle(&self, matrix: &[Vec<u8>], empties: &[[usize; 2]]) -> Option<&Record> {
+        let mut best: Option<&Record> = None;
+        for r in &self.records {
+            if !r.is_win || r.matrix != matrix || r.empties != empties {
+                continue;
+            }
+            match best {
+                Some(b) if b.duration <= r.duration => {}
+                _ => { best = Some(r) }
+            }
+        }
+        return best;
+    }
+
+    pub fn best_score(&self, difficult: Difficult) -> Option<i64> {
+        return self.records.iter()
+            .filter(|r| r.difficult == difficult && r.is_win)
+            .map(|r| r.score)
+            .max();
+    }
+}
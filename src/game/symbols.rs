@@ -0,0 +1,174 @@
+use macroquad::prelude::*;
+
+const DIGITS: &str = "123456789ABCDEFG";
+// Nine different letters, each typed with its own key.
+const WORD: &str = "WORDSMITH";
+
+/// What the player puts into cells instead of the digits the rules work with.
+/// All sets but digits have nine symbols and are only used on 9x9 grids.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SymbolSet {
+    Digits,
+    Word,
+    Colors,
+    Shapes,
+}
+
+impl SymbolSet {
+    pub fn next(&self) -> SymbolSet {
+        match self {
+            SymbolSet::Digits => SymbolSet::Word,
+            SymbolSet::Word => SymbolSet::Colors,
+            SymbolSet::Colors => SymbolSet::Shapes,
+            SymbolSet::Shapes => SymbolSet::Digits,
+        }
+    }
+
+    /// Text of a 0-based digit, empty for the sets that are drawn.
+    pub fn label(&self, num: u8) -> String {
+        let chars = match self {
+            SymbolSet::Digits => DIGITS,
+            SymbolSet::Word => WORD,
+            _ => { return "".to_owned(); }
+        };
+        return chars.chars().nth(num as usize).unwrap().to_string();
+    }
+
+    /// Digit (1-based) typed with `code`.
+    pub fn num_by_key(&self, code: KeyCode) -> Option<u8> {
+        if *self == SymbolSet::Word {
+            return WORD.chars().position(|c| SymbolSet::letter_key(c) == Some(code)).map(|i| i as u8 + 1);
+        }
+        match code {
+            KeyCode::Key1 | KeyCode::Kp1 => Some(1),
+            KeyCode::Key2 | KeyCode::Kp2 => Some(2),
+            KeyCode::Key3 | KeyCode::Kp3 => Some(3),
+            KeyCode::Key4 | KeyCode::Kp4 => Some(4),
+            KeyCode::Key5 | KeyCode::Kp5 => Some(5),
+            KeyCode::Key6 | KeyCode::Kp6 => Some(6),
+            KeyCode::Key7 | KeyCode::Kp7 => Some(7),
+            KeyCode::Key8 | KeyCode::Kp8 => Some(8),
+            KeyCode::Key9 | KeyCode::Kp9 => Some(9),
+            KeyCode::A => Some(10),
+            KeyCode::B => Some(11),
+            KeyCode::C => Some(12),
+            KeyCode::D => Some(13),
+            KeyCode::E => Some(14),
+            KeyCode::F => Some(15),
+            KeyCode::G => Some(16),
+            _ => None,
+        }
+    }
+
+    /// Key of a 1-based digit, the number pad sends it like the keyboard does.
+    pub fn key_by_num(&self, num: u8) -> KeyCode {
+        if *self == SymbolSet::Word {
+            return SymbolSet::letter_key(WORD.chars().nth(num as usize - 1).unwrap()).unwrap();
+        }
+        match num {
+            1 => KeyCode::Key1,
+            2 => KeyCode::Key2,
+            3 => KeyCode::Key3,
+            4 => KeyCode::Key4,
+            5 => KeyCode::Key5,
+            6 => KeyCode::Key6,
+            7 => KeyCode::Key7,
+            8 => KeyCode::Key8,
+            9 => KeyCode::Key9,
+            10 => KeyCode::A,
+            11 => KeyCode::B,
+            12 => KeyCode::C,
+            13 => KeyCode::D,
+            14 => KeyCode::E,
+            15 => KeyCode::F,
+            _ => KeyCode::G,
+        }
+    }
+
+    fn letter_key(c: char) -> Option<KeyCode> {
+        match c {
+            'A' => Some(KeyCode::A),
+            'B' => Some(KeyCode::B),
+            'C' => Some(KeyCode::C),
+            'D' => Some(KeyCode::D),
+            'E' => Some(KeyCode::E),
+            'F' => Some(KeyCode::F),
+            'G' => Some(KeyCode::G),
+            'H' => Some(KeyCode::H),
+            'I' => Some(KeyCode::I),
+            'J' => Some(KeyCode::J),
+            'K' => Some(KeyCode::K),
+            'L' => Some(KeyCode::L),
+            'M' => Some(KeyCode::M),
+            'N' => Some(KeyCode::N),
+            'O' => Some(KeyCode::O),
+            'P' => Some(KeyCode::P),
+            'Q' => Some(KeyCode::Q),
+            'R' => Some(KeyCode::R),
+            'S' => Some(KeyCode::S),
+            'T' => Some(KeyCode::T),
+            'U' => Some(KeyCode::U),
+            'V' => Some(KeyCode::V),
+            'W' => Some(KeyCode::W),
+            'X' => Some(KeyCode::X),
+            'Y' => Some(KeyCode::Y),
+            'Z' => Some(KeyCode::Z),
+            _ => None,
+        }
+    }
+
+    /// Colours and shapes are drawn instead of written.
+    pub fn is_text(&self) -> bool {
+        return *self == SymbolSet::Digits || *self == SymbolSet::Word;
+    }
+
+    /// Draws a 0-based digit of a picture set centered at `x`, `y`. `color` is
+    /// the state of the cell (given, entered, selected, wrong), colours show it as a ring.
+    pub fn draw_picture(&self, num: u8, x: f32, y: f32, r: f32, color: Color) {
+        match self {
+            SymbolSet::Colors => {
+                draw_circle(x, y, r, SymbolSet::palette(num));
+                draw_circle_lines(x, y, r + 2., 2., color);
+            }
+            SymbolSet::Shapes => SymbolSet::draw_shape(num, x, y, r, color),
+            _ => {}
+        }
+    }
+
+    fn palette(num: u8) -> Color {
+        match num {
+            0 => Color::from_rgba(230, 57, 70, 255),
+            1 => Color::from_rgba(244, 162, 97, 255),
+            2 => Color::from_rgba(250, 214, 64, 255),
+            3 => Color::from_rgba(106, 190, 80, 255),
+            4 => Color::from_rgba(42, 157, 143, 255),
+            5 => Color::from_rgba(69, 123, 230, 255),
+            6 => Color::from_rgba(140, 82, 200, 255),
+            7 => Color::from_rgba(240, 120, 190, 255),
+            _ => Color::from_rgba(120, 80, 50, 255),
+        }
+    }
+
+    fn draw_shape(num: u8, x: f32, y: f32, r: f32, color: Color) {
+        match num {
+            0 => draw_circle(x, y, r, color),
+            1 => draw_rectangle(x - r, y - r, r * 2., r * 2., color),
+            2 => draw_triangle(vec2(x, y - r), vec2(x - r, y + r), vec2(x + r, y + r), color),
+            3 => {
+                draw_triangle(vec2(x, y - r), vec2(x - r, y), vec2(x + r, y), color);
+                draw_triangle(vec2(x, y + r), vec2(x - r, y), vec2(x + r, y), color);
+            }
+            4 => draw_circle_lines(x, y, r, 2., color),
+            5 => draw_rectangle_lines(x - r, y - r, r * 2., r * 2., 4., color),
+            6 => {
+                draw_rectangle(x - r, y - r / 4., r * 2., r / 2., color);
+                draw_rectangle(x - r / 4., y - r, r / 2., r * 2., color);
+            }
+            7 => {
+                draw_line(x - r, y - r, x + r, y + r, r / 2., color);
+                draw_line(x - r, y + r, x + r, y - r, r / 2., color);
+            }
+            _ => draw_triangle(vec2(x, y + r), vec2(x - r, y - r), vec2(x + r, y - r), color),
+        }
+    }
+}
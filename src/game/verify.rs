@@ -0,0 +1,157 @@
+//! Checks a solve the game reports, for the leaderboard server. The puzzle is
+//! built again from its seed and the player's moves are played on it.
+
+use crate::game::generator::{self, Chess, Difficult, Generated, Variant};
+use crate::game::replay::{Action, Move};
+
+// Two digits in different cells closer than this can't come from a person:
+// each needs a cell picked and a digit pressed.
+pub const MIN_PLACE_GAP: f64 = 0.15;
+// Solves faster than this per empty cell are kept but flagged.
+pub const SUSPICIOUS_CELL_TIME: f64 = 1.5;
+// times are rounded when sent, the last move may be a bit after `elapsed`
+const TIME_SLACK: f64 = 0.1;
+
+/// What the player says about the solve, as the "won" event has it.
+pub struct Claim<'a> {
+    pub variant: Variant,
+    pub chess: Chess,
+    pub size: usize,
+    pub difficult: Difficult,
+    pub seed: u64,
+    pub elapsed: f64,
+    pub hints: u32,
+    pub moves: &'a [Move],
+}
+
+/// Err with the reason when the moves can't have solved the puzzle, otherwise
+/// the names of the suspicious things about them, empty for a clean solve.
+pub fn verify(claim: &Claim) -> Result<Vec<&'static str>, String> {
+    let generated = match generator::generate(claim.variant, claim.chess, claim.size, claim.difficult, claim.seed, true) {
+        None => { return Err("the settings can't make a puzzle".to_string()); }
+        Some(generated) => generated,
+    };
+    return verify_with(&generated, claim);
+}
+
+/// Same as `verify` on a puzzle already built from the claim's settings and seed.
+pub fn verify_with(generated: &Generated, claim: &Claim) -> Result<Vec<&'static str>, String> {
+    if !claim.elapsed.is_finite() || claim.elapsed <= 0. {
+        return Err("elapsed must be positive".to_string());
+    }
+    let rules = &generated.rules;
+    let mut board = generated.puzzle.clone();
+    let mut previous = 0.;
+    // time and cell of the last digit put by the player
+    let mut last_place: Option<(f64, usize)> = None;
+    let mut hints = 0;
+
+    for (i, m) in claim.moves.iter().enumerate() {
+        if !m.time.is_finite() || m.time < previous || m.time > claim.elapsed + TIME_SLACK {
+            return Err(format!("move {} is out of order", i + 1));
+        }
+        previous = m.time;
+        let (x, y, num) = match m.action {
+            Action::Place { x, y, num } | Action::Hint { x, y, num } => (x, y, num),
+            Action::Erase { x, y } => (x, y, 0),
+            Action::Select { .. } | Action::Check | Action::Note { .. } => { continue; }
+        };
+        if x >= rules.width || y >= rules.width || !rules.active[y * rules.width + x] {
+            return Err(format!("move {} is off the board", i + 1));
+        }
+        let cell = y * rules.width + x;
+        if generated.puzzle[cell] != 0 {
+            return Err(format!("move {} changes a given", i + 1));
+        }
+        let is_erase = matches!(m.action, Action::Erase { .. });
+        if num as usize > claim.size || (num == 0 && !is_erase) {
+            return Err(format!("move {} puts {}", i + 1, num));
+        }
+        match m.action {
+            Action::Place { .. } => {
+                if let Some((time, last)) = last_place {
+                    if last != cell && m.time - time < MIN_PLACE_GAP {
+                        return Err(format!("move {} comes {:.3}s after the previous digit", i + 1, m.time - time));
+                    }
+                }
+                last_place = Some((m.time, cell));
+            }
+            Action::Hint { .. } => {
+                if num != generated.solution[cell] {
+                    return Err(format!("move {} is a wrong hint", i + 1));
+                }
+                hints += 1;
+            }
+            _ => {}
+        }
+        board[cell] = num;
+    }
+
+    if board != generated.solution {
+        return Err("the final grid isn't the solution".to_string());
+    }
+    let mut flags = vec![];
+    let empties = generated.puzzle.iter().enumerate().filter(|(c, n)| **n == 0 && rules.active[*c]).count();
+    if claim.elapsed < SUSPICIOUS_CELL_TIME * empties as f64 {
+        flags.push("fast");
+    }
+    if hints != claim.hints {
+        flags.push("hints");
+    }
+    if !claim.moves.iter().any(|m| matches!(m.action, Action::Select { .. })) {
+        // every game picks cells before putting digits, a log without it was made up
+        flags.push("no_selects");
+    }
+    return Ok(flags);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEED: u64 = 5;
+
+    fn claim(moves: &[Move], elapsed: f64) -> Claim<'_> {
+        return Claim {
+            variant: Variant::Classic,
+            chess: Chess::Off,
+            size: 4,
+            difficult: Difficult::Easy,
+            seed: SEED,
+            elapsed,
+            hints: 0,
+            moves,
+        };
+    }
+
+    // picks and fills every empty cell, two seconds apart
+    fn solve(generated: &Generated) -> Vec<Move> {
+        let width = generated.rules.width;
+        let mut moves = vec![];
+        let mut time = 0.;
+        for (cell, num) in generated.puzzle.iter().enumerate() {
+            if *num != 0 {
+                continue;
+            }
+            time += 2.;
+            let (x, y) = (cell % width, cell / width);
+            moves.push(Move { time, action: Action::Select { x, y } });
+            moves.push(Move { time: time + 1., action: Action::Place { x, y, num: generated.solution[cell] } });
+        }
+        return moves;
+    }
+
+    #[test]
+    fn times_must_be_finite() {
+        let generated = generator::generate(Variant::Classic, Chess::Off, 4, Difficult::Easy, SEED, true).unwrap();
+        let mut moves = solve(&generated);
+        let elapsed = moves.last().unwrap().time;
+        assert_eq!(verify_with(&generated, &claim(&moves, elapsed)), Ok(vec![]));
+        assert_eq!(verify(&claim(&moves, elapsed)), Ok(vec![]));
+
+        assert!(verify_with(&generated, &claim(&moves, f64::INFINITY)).is_err());
+        moves.last_mut().unwrap().time = f64::INFINITY;
+        assert!(verify_with(&generated, &claim(&moves, elapsed)).is_err());
+        assert!(verify_with(&generated, &claim(&moves, f64::INFINITY)).is_err());
+    }
+}
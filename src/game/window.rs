@@ -0,0 +1,1074 @@
+use std::collections::HashMap;
+
+use macroquad::prelude::*;
+use macroquad::ui::{root_ui};
+use macroquad::time;
+
+use crate::game::replay::{Action, Ghost, Replay};
+use crate::game::constraints::{Constraint, Relation};
+use crate::game::generator::{Chess, Difficult, Variant};
+use crate::game::model::{Key, Model};
+use crate::game::rating;
+use crate::game::solver::{Rng, Rules, LOAD_BUDGET};
+use crate::game::symbols::SymbolSet;
+
+#[derive(Debug)]
+struct Circle {
+    x: f32,
+    y: f32,
+    r: f32,
+    key: KeyCode,
+}
+
+// Width of the grid in pixels, the cell size is this divided by the grid size.
+const GRID_WIDTH: usize = 360;
+// Spacing unit of the buttons under the grid, doesn't depend on the grid size.
+const PAD_OFFSET: f32 = 40.0;
+const SIZES: [usize; 5] = [9, 4, 6, 12, 16];
+// Guesses the editor spends on counting solutions after every change.
+const EDITOR_BUDGET: usize = 2_000;
+// Cells shown across at each zoom level of the samurai board.
+const ZOOM_STEPS: [usize; 3] = [21, 14, 9];
+
+pub enum Screens {
+    Start,
+    Game,
+    Replay,
+    Editor,
+}
+
+pub struct Game {
+    pub font_size: f32,
+    pub start_y: f32,
+    pub start_x: usize,
+    // the puzzle, the player's digits, the timer and the score
+    pub model: Model,
+    pub symbols: SymbolSet,
+    pub current_screen: Screens,
+    pub replay: Option<Replay>,
+    // solutions of the puzzle in the editor: 0, 1, 2 for many, None when unknown
+    pub editor_solutions: Option<usize>,
+    // rating of the edited puzzle while it has one solution
+    editor_difficult: Difficult,
+    pub editor_export: Option<String>,
+    ghost: Option<Ghost>,
+    last_tick: Option<f64>,
+    pub end_y: f32,
+    // first shown cell and how many cells fit across, the board may not fit the screen
+    view_x: usize,
+    view_y: usize,
+    visible: usize,
+    offset: usize,
+    cell_font_size: f32,
+    pub end_x: usize,
+    textures: HashMap<String, Texture2D>,
+    numbers_coord: Vec<Circle>,
+}
+
+/// Difficulty by the hardest technique the puzzle needs, Hard when logic
+/// alone doesn't solve it.
+fn rate_puzzle(rules: &Rules, puzzle: &[u8]) -> Difficult {
+    return match rating::hardest(rules, puzzle) {
+        None => Difficult::Hard,
+        Some(technique) => Difficult::by_technique(technique),
+    };
+}
+
+impl Game {
+    pub async fn new(screen_height: f32, screen_width: f32, current_difficult: Difficult) -> Self {
+        let steps = 9.0;
+        let offset: usize = 40;
+        let start_y: f32 = screen_height / 2.0 - offset as f32 * steps / 1.5;
+        let end_y: f32 = start_y as f32 + offset as f32 * steps;
+        let start_x: usize = (screen_width / 2.0 - offset as f32 * steps / 2.0) as usize;
+        let end_x: usize = (start_x as f32 + offset as f32 * steps) as usize;
+        // classic 9x9 always has a grid
+        let mut model = Model::new(Variant::Classic, Chess::Off, 9, current_difficult, Game::new_seed()).unwrap();
+        // the start screen comes first, nothing has started yet
+        model.take_events();
+
+        let color_circle: Texture2D = load_texture("assets/color_circle.png").await.unwrap();
+        let textures: HashMap<String, Texture2D> = HashMap::from([
+            ("color_circle".to_string(), color_circle),
+        ]);
+
+        return Game {
+            font_size: 25.0,
+            start_y,
+            end_y,
+            view_x: 0,
+            view_y: 0,
+            visible: 9,
+            offset,
+            cell_font_size: 25.0,
+            start_x,
+            end_x,
+            model,
+            symbols: SymbolSet::Digits,
+            current_screen: Screens::Start,
+            textures,
+            numbers_coord: vec![],
+            replay: None,
+            editor_solutions: None,
+            editor_difficult: Difficult::Hard,
+            editor_export: None,
+            ghost: None,
+            last_tick: None,
+        };
+    }
+
+    /// Starts a new puzzle with the chosen settings, false when they can't make one.
+    pub fn regenerate(&mut self) -> bool {
+        if !self.model.generate(Game::new_seed()) {
+            return false;
+        }
+        self.new_board();
+        return true;
+    }
+
+    /// Starts today's puzzle for the chosen difficulty, variant and size.
+    pub fn play_daily(&mut self) -> bool {
+        let day = (miniquad::date::now() / 86400.).floor() as i64;
+        if !self.model.play_daily(day) {
+            return false;
+        }
+        self.new_board();
+        return true;
+    }
+
+    /// Resets what the window keeps about the board after the model got a new one.
+    fn new_board(&mut self) {
+        self.ghost = self.best_ghost();
+        self.replay = None;
+        self.view_x = 0;
+        self.view_y = 0;
+        self.set_view(self.model.width);
+        self.last_tick = Some(time::get_time());
+    }
+
+    /// Shows `visible` cells across, the cell size grows so the grid keeps its width.
+    fn set_view(&mut self, visible: usize) {
+        self.visible = visible;
+        self.offset = GRID_WIDTH / visible;
+        self.cell_font_size = self.offset as f32 * 0.625;
+        self.end_x = self.start_x + self.offset * visible;
+        self.end_y = self.start_y + (self.offset * visible) as f32;
+        self.view_x = self.view_x.min(self.model.width - visible);
+        self.view_y = self.view_y.min(self.model.width - visible);
+    }
+
+    pub fn can_zoom(&self) -> bool {
+        return self.model.current_variant == Variant::Samurai;
+    }
+
+    pub fn zoom_in(&mut self) {
+        match ZOOM_STEPS.iter().find(|v| **v < self.visible) {
+            None => {}
+            Some(v) => self.zoom(*v),
+        }
+    }
+
+    pub fn zoom_out(&mut self) {
+        match ZOOM_STEPS.iter().rev().find(|v| **v > self.visible && **v <= self.model.width) {
+            None => {}
+            Some(v) => self.zoom(*v),
+        }
+    }
+
+    /// Zooms around the selected cell, or around the middle of the view without one.
+    fn zoom(&mut self, visible: usize) {
+        let (x, y) = match self.model.selected() {
+            None => (self.view_x + self.visible / 2, self.view_y + self.visible / 2),
+            Some(coord) => (coord[0], coord[1]),
+        };
+        self.view_x = x.saturating_sub(visible / 2);
+        self.view_y = y.saturating_sub(visible / 2);
+        self.set_view(visible);
+    }
+
+    /// Moves the view with the arrow keys or the mouse wheel when the board doesn't fit.
+    fn scroll_view(&mut self) {
+        if self.visible == self.model.width {
+            return;
+        }
+        let (wheel_x, wheel_y) = mouse_wheel();
+        let mut dx = wheel_x.signum() as i32;
+        let mut dy = -wheel_y.signum() as i32;
+        if is_key_pressed(KeyCode::Left) { dx = -1 }
+        if is_key_pressed(KeyCode::Right) { dx = 1 }
+        if is_key_pressed(KeyCode::Up) { dy = -1 }
+        if is_key_pressed(KeyCode::Down) { dy = 1 }
+        let max = (self.model.width - self.visible) as i32;
+        self.view_x = (self.view_x as i32 + dx).clamp(0, max) as usize;
+        self.view_y = (self.view_y as i32 + dy).clamp(0, max) as usize;
+    }
+
+    fn cell_x(&self, x: usize) -> f32 {
+        return self.start_x as f32 + (self.offset * (x - self.view_x)) as f32;
+    }
+
+    fn cell_y(&self, y: usize) -> f32 {
+        return self.start_y + (self.offset * (y - self.view_y)) as f32;
+    }
+
+    fn new_seed() -> u64 {
+        return (miniquad::date::now() * 1000.) as u64;
+    }
+
+    /// Variants other than classic are only generated for 9x9, switching the
+    /// size goes back to classic.
+    pub fn switch_size(&mut self) {
+        let i = SIZES.iter().position(|s| *s == self.model.size).unwrap_or(0);
+        self.model.size = SIZES[(i + 1) % SIZES.len()];
+        if self.model.size != 9 {
+            self.model.current_variant = Variant::Classic;
+            self.model.chess = Chess::Off;
+            self.symbols = SymbolSet::Digits;
+        }
+    }
+
+    pub fn switch_variant(&mut self) {
+        self.model.current_variant = match self.model.current_variant {
+            Variant::Classic => Variant::Diagonal,
+            Variant::Diagonal => Variant::Killer,
+            Variant::Killer => Variant::Jigsaw,
+            Variant::Jigsaw => Variant::Samurai,
+            Variant::Samurai => Variant::Windoku,
+            Variant::Windoku => Variant::Inequality,
+            Variant::Inequality => Variant::Kropki,
+            Variant::Kropki => Variant::Classic,
+        };
+        if self.model.current_variant != Variant::Classic {
+            self.model.size = 9;
+            self.model.chess = Chess::Off;
+        }
+    }
+
+    /// Symbol sets other than digits have nine symbols, so they switch to 9x9.
+    pub fn switch_symbols(&mut self) {
+        self.symbols = self.symbols.next();
+        if self.symbols != SymbolSet::Digits {
+            self.model.size = 9;
+        }
+    }
+
+    /// Chess rules are only generated for classic 9x9.
+    pub fn switch_chess(&mut self) {
+        self.model.chess = match self.model.chess {
+            Chess::Off => Chess::Knight,
+            Chess::Knight => Chess::King,
+            Chess::King => Chess::Both,
+            Chess::Both => Chess::Off,
+        };
+        if self.model.chess != Chess::Off {
+            self.model.current_variant = Variant::Classic;
+            self.model.size = 9;
+        }
+    }
+
+    /// Opens an empty classic grid of the current size, every digit put in
+    /// the editor is a given of the new puzzle.
+    pub fn open_editor(&mut self) {
+        let size = self.model.size;
+        self.model.current_variant = Variant::Classic;
+        self.model.chess = Chess::Off;
+        self.model.set_board(Rules::classic(size), &vec![0; size * size], &vec![0; size * size]);
+        self.new_board();
+        self.editor_export = None;
+        self.current_screen = Screens::Editor;
+        self.update_editor();
+    }
+
+    fn is_editing(&self) -> bool {
+        matches!(self.current_screen, Screens::Editor)
+    }
+
+    /// Marks conflicting givens and counts solutions of the edited grid.
+    fn update_editor(&mut self) {
+        let grid = self.model.current_grid();
+        let conflicts = self.model.rules.conflicts(&grid);
+        let width = self.model.width;
+        self.model.no_valid = conflicts.iter().map(|cell| [cell % width, cell / width]).collect();
+        self.editor_export = None;
+        if !conflicts.is_empty() {
+            self.editor_solutions = Some(0);
+            return;
+        }
+        self.editor_solutions = self.model.rules.try_count_solutions(&grid, 2, EDITOR_BUDGET);
+        if self.editor_solutions == Some(1) {
+            self.editor_difficult = rate_puzzle(&self.model.rules, &grid);
+        }
+    }
+
+    pub fn get_editor_solutions(&self) -> String {
+        match self.editor_solutions {
+            None => "Решений: ?".to_owned(),
+            Some(0) => "Нет решений".to_owned(),
+            Some(1) => "Одно решение".to_owned(),
+            Some(_) => "Много решений".to_owned(),
+        }
+    }
+
+    /// Difficulty by the hardest technique the edited puzzle needs, kept from
+    /// the last edit that left it with one solution.
+    pub fn get_editor_difficult(&self) -> Difficult {
+        return self.editor_difficult;
+    }
+
+    pub fn export_editor(&mut self) {
+        self.editor_export = Some(self.model.get_board());
+    }
+
+    /// Starts playing the edited puzzle, only when its solution is unique.
+    pub fn play_editor(&mut self) -> bool {
+        if self.editor_solutions != Some(1) {
+            return false;
+        }
+        let puzzle = self.model.current_grid();
+        let mut solution = puzzle.clone();
+        // counting already went through every guess filling can make
+        let mut budget = EDITOR_BUDGET;
+        if !self.model.rules.fill(&mut solution, &mut Rng::new(self.model.seed), &mut budget) {
+            return false;
+        }
+        let difficult = self.get_editor_difficult();
+        let rules = Rules::classic(self.model.size);
+        self.model.load(rules, &solution, &puzzle, difficult);
+        self.new_board();
+        self.current_screen = Screens::Game;
+        return true;
+    }
+
+    /// Starts a classic puzzle passed by the page, `puzzle` must have one solution.
+    pub fn load_puzzle(&mut self, size: usize, puzzle: &[u8]) -> bool {
+        let rules = Rules::classic(size);
+        let mut solution = puzzle.to_vec();
+        let mut budget = LOAD_BUDGET;
+        if !rules.fill(&mut solution, &mut Rng::new(self.model.seed), &mut budget) {
+            return false;
+        }
+        let difficult = rate_puzzle(&rules, puzzle);
+        if size != 9 {
+            self.symbols = SymbolSet::Digits;
+        }
+        self.model.load(rules, &solution, puzzle, difficult);
+        self.new_board();
+        self.current_screen = Screens::Game;
+        return true;
+    }
+
+    /// The best previous solve of the current puzzle to race, if there is one.
+    fn best_ghost(&self) -> Option<Ghost> {
+        let best = self.model.stats.best_for_puzzle(&self.model.matrix, &self.model.get_empties());
+        best.map(|r| Ghost::new(&r.moves, r.duration, &r.matrix))
+    }
+
+    /// Starts the current puzzle again, racing the best previous solve of it.
+    pub fn restart(&mut self) {
+        self.ghost = self.best_ghost();
+        self.model.restart();
+        self.replay = None;
+        self.last_tick = Some(time::get_time());
+    }
+
+    /// Goes back to the start screen, an unfinished game counts as abandoned.
+    pub fn leave(&mut self) {
+        self.model.leave();
+        self.current_screen = Screens::Start;
+    }
+
+    /// Difference to the ghost formatted like "+0:05", None without a ghost.
+    pub fn get_ghost_delta(&self) -> Option<(String, bool)> {
+        let ghost = match &self.ghost {
+            None => { return None; }
+            Some(ghost) => ghost,
+        };
+        let mut correct = 0;
+        for (key, v) in &self.model.user_matrix {
+            if self.model.matrix[key.y][key.x] == v - 1 {
+                correct += 1;
+            }
+        }
+        return match ghost.delta(correct, self.model.elapsed, self.model.is_finish) {
+            None => None,
+            Some(delta) => {
+                let sign = if delta > 0. { "+" } else { "-" };
+                let seconds = delta.abs() as i64;
+                Some((format!("{}{}:{:02}", sign, seconds / 60, seconds % 60), delta <= 0.))
+            }
+        };
+    }
+
+    /// Adds the time passed since the previous frame to the game time.
+    pub fn tick(&mut self) {
+        let now = time::get_time();
+        if let Some(last_tick) = self.last_tick {
+            self.model.tick(now - last_tick);
+        }
+        self.last_tick = Some(now);
+    }
+
+    pub fn resume(&mut self) {
+        self.model.resume();
+        self.last_tick = Some(time::get_time());
+    }
+
+    /// Loads the puzzle of the last finished game and starts playing its moves back.
+    pub fn start_replay(&mut self) -> bool {
+        let record = match self.model.stats.last() {
+            None => { return false; }
+            Some(r) => r.clone(),
+        };
+        let model = &mut self.model;
+        model.current_difficult = record.difficult;
+        model.current_variant = record.variant;
+        model.chess = record.chess;
+        model.size = record.size;
+        model.width = record.rules.width;
+        model.rules = record.rules;
+        model.matrix = record.matrix;
+        model.empties = Default::default();
+        for coord in record.empties {
+            model.empties.insert(Key { x: coord[0], y: coord[1] }, true);
+        }
+        self.new_board();
+        self.ghost = None;
+        self.replay = Some(Replay::new(record.moves, record.duration));
+        self.apply_replay();
+        return true;
+    }
+
+    pub fn update_replay(&mut self, delta: f64) {
+        match self.replay.as_mut() {
+            None => { return; }
+            Some(replay) => { replay.advance(delta); }
+        }
+        self.apply_replay();
+    }
+
+    /// Rebuilds the board from scratch with all moves made before the current
+    /// replay position, so scrubbing backwards works the same as forwards.
+    fn apply_replay(&mut self) {
+        let (moves, position) = match &self.replay {
+            None => { return; }
+            Some(replay) => (replay.visible_moves(), replay.position),
+        };
+        let model = &mut self.model;
+        model.user_matrix = Default::default();
+        model.notes = vec![0; model.width * model.width];
+        model.marked_coord = vec![];
+        model.no_valid = vec![];
+        for m in moves {
+            match m.action {
+                Action::Select { x, y } => {
+                    model.marked_coord = vec!([x, y]);
+                    model.no_valid = vec![];
+                }
+                Action::Place { x, y, num } | Action::Hint { x, y, num } => {
+                    model.clear_notes(x, y, num);
+                    model.user_matrix.insert(Key { x, y }, num);
+                }
+                Action::Erase { x, y } => {
+                    model.notes[y * model.width + x] = 0;
+                    model.user_matrix.remove(&Key { x, y });
+                }
+                Action::Check => { model.find_no_valid(); }
+                Action::Note { x, y, num } => { model.notes[y * model.width + x] ^= 1 << num; }
+            }
+        }
+        model.elapsed = position;
+    }
+
+    fn in_numbers_coord(&self, mouse_x: f32, mouse_y: f32) -> Option<KeyCode> {
+        for c in &self.numbers_coord {
+            if mouse_x < c.x - c.r {
+                continue;
+            }
+            if mouse_x > c.x + c.r {
+                continue;
+            }
+            if mouse_y < c.y - c.r {
+                continue;
+            }
+            if mouse_y > c.y + c.r {
+                continue;
+            }
+            return Some(c.key.clone());
+        }
+        return None;
+    }
+
+    fn fill_num(&mut self, code: Option<KeyCode>) {
+        match code {
+            None => {}
+            Some(code) => {
+                let [x, y] = match self.model.selected() {
+                    None => { return; }
+                    Some(coord) => coord,
+                };
+                if !self.model.empties.contains_key(&Key { x, y }) {
+                    return;
+                }
+                let num = match code {
+                    KeyCode::Delete | KeyCode::KpDecimal | KeyCode::Backspace => None,
+                    _ => match self.symbols.num_by_key(code) {
+                        Some(num) if num as usize <= self.model.size => Some(num),
+                        _ => { return; }
+                    },
+                };
+                if self.is_editing() {
+                    // digits put in the editor are givens, they don't go to the move log
+                    match num {
+                        None => { self.model.user_matrix.remove(&Key { x, y }); }
+                        Some(num) => { self.model.user_matrix.insert(Key { x, y }, num); }
+                    }
+                    self.update_editor();
+                    return;
+                }
+                match num {
+                    None => self.model.erase_num(x, y),
+                    Some(num) => self.model.place_num(x, y, num),
+                }
+            }
+        }
+    }
+
+    fn coord_by_num(&self, num: u8) -> Vec<[usize; 2]> {
+        let mut data: Vec<[usize; 2]> = vec!();
+        for y in (0..self.model.width).step_by(1) {
+            for x in (0..self.model.width).step_by(1) {
+                if num != self.model.matrix[y][x] || !self.model.is_active(x, y) {
+                    continue;
+                }
+                data.push([x, y]);
+            }
+        }
+        return data;
+    }
+
+    fn coord_by_position(&self, mouse_x: f32, mouse_y: f32) -> (usize, usize) {
+        let mut x = ((mouse_x - self.start_x as f32) / self.offset as f32) as usize;
+        let mut y = ((mouse_y - self.start_y as f32) / self.offset as f32) as usize;
+        if x > self.visible - 1 {
+            x = self.visible - 1;
+        }
+        if y > self.visible - 1 {
+            y = self.visible - 1;
+        }
+        return (x + self.view_x, y + self.view_y);
+    }
+
+    fn in_window(&self, mouse_x: f32, mouse_y: f32) -> bool {
+        if mouse_x < self.start_x as f32 {
+            return false;
+        }
+        if mouse_x > self.end_x as f32 {
+            return false;
+        }
+        if mouse_y > self.end_y as f32 {
+            return false;
+        }
+        if mouse_y < self.start_y {
+            return false;
+        }
+        return true;
+    }
+}
+
+pub trait UI {
+    fn draw_form(&self);
+    fn draw_cages(&self);
+    fn draw_pairs(&self);
+    fn draw_chess_conflicts(&self);
+    fn draw_number_buttons(&mut self);
+    fn draw_hit_buttons(&mut self);
+    fn draw_zoom_buttons(&mut self);
+    fn draw_pause_button(&mut self);
+    fn draw_pause_screen(&self, font: Font);
+    fn draw_game_screen(&mut self, font: Font, mouse_x: f32, mouse_y: f32, is_finish: bool);
+    fn draw_marker(&self, x: f32, y: f32);
+    fn draw_numbers(&self, need_mark: Vec<[usize; 2]>, font: Font);
+    fn draw_replay_screen(&mut self, font: Font, mouse_x: f32, mouse_y: f32);
+}
+
+impl UI for Game {
+    fn draw_form(&self) {
+        if self.model.current_variant == Variant::Diagonal {
+            for i in 0..self.model.size {
+                for x in [i, self.model.size - 1 - i] {
+                    draw_rectangle(
+                        self.cell_x(x),
+                        self.cell_y(i),
+                        self.offset as f32,
+                        self.offset as f32,
+                        Color::from_rgba(240, 249, 255, 255),
+                    );
+                }
+            }
+        }
+        if self.model.current_variant == Variant::Windoku {
+            for y in 0..self.model.size {
+                for x in 0..self.model.size {
+                    // the extra boxes are everything between rows and columns 0, 4 and 8
+                    if x % 4 == 0 || y % 4 == 0 {
+                        continue;
+                    }
+                    draw_rectangle(self.cell_x(x), self.cell_y(y), self.offset as f32, self.offset as f32, Color::from_rgba(240, 249, 255, 255));
+                }
+            }
+        }
+        let def_thickness: f32 = 1.0;
+        let def_color = Color::from_rgba(219, 219, 219, 255);
+        let region_color = Color::from_rgba(125, 208, 255, 255);
+        let offset = self.offset as f32;
+        let regions = &self.model.rules.regions;
+        let width = self.model.width;
+        // cells outside of the samurai grids have no region, nothing is drawn between them
+        let outside = usize::MAX;
+        for line in 1..self.visible {
+            let line_offset = offset * line as f32;
+            for i in 0..self.visible {
+                let cell_offset = offset * i as f32;
+                let (row, column) = (self.view_y + line, self.view_x + i);
+
+                // between rows line - 1 and line
+                let y = self.start_y + line_offset;
+                let x1 = self.start_x as f32 + cell_offset;
+                let (a, b) = (regions[(row - 1) * width + column], regions[row * width + column]);
+                if a != b {
+                    draw_line(x1, y, x1 + offset, y, def_thickness * 2.0, region_color);
+                } else if a != outside {
+                    draw_line(x1 + 5., y, x1 + offset - 5., y, def_thickness, def_color);
+                }
+
+                // between columns line - 1 and line
+                let (row, column) = (self.view_y + i, self.view_x + line);
+                let x = self.start_x as f32 + line_offset;
+                let y1 = self.start_y + cell_offset;
+                let (a, b) = (regions[row * width + column - 1], regions[row * width + column]);
+                if a != b {
+                    draw_line(x, y1, x, y1 + offset, def_thickness * 2.0, region_color);
+                } else if a != outside {
+                    draw_line(x, y1 + 5., x, y1 + offset - 5., def_thickness, def_color);
+                }
+            }
+        }
+        self.draw_cages();
+        self.draw_pairs();
+        self.draw_chess_conflicts();
+    }
+
+    fn draw_cages(&self) {
+        let color = Color::from_rgba(141, 141, 141, 255);
+        let inset = 4.;
+        let offset = self.offset as f32;
+        for constraint in &self.model.rules.constraints {
+            let cage = match constraint {
+                Constraint::Cage(cage) => cage,
+                _ => { continue; }
+            };
+            for &cell in &cage.cells {
+                let (x, y) = (cell % self.model.width, cell / self.model.width);
+                let x1 = self.cell_x(x) + inset;
+                let y1 = self.cell_y(y) + inset;
+                let x2 = x1 + offset - inset * 2.;
+                let y2 = y1 + offset - inset * 2.;
+                if x == 0 || !cage.cells.contains(&(cell - 1)) {
+                    draw_line(x1, y1, x1, y2, 1., color);
+                }
+                if x == self.model.width - 1 || !cage.cells.contains(&(cell + 1)) {
+                    draw_line(x2, y1, x2, y2, 1., color);
+                }
+                if y == 0 || !cage.cells.contains(&(cell - self.model.width)) {
+                    draw_line(x1, y1, x2, y1, 1., color);
+                }
+                if y == self.model.width - 1 || !cage.cells.contains(&(cell + self.model.width)) {
+                    draw_line(x1, y2, x2, y2, 1., color);
+                }
+            }
+            let first = cage.cells[0];
+            let x = self.cell_x(first % self.model.width);
+            let y = self.cell_y(first / self.model.width);
+            draw_text(&cage.sum.to_string(), x + inset + 1., y + inset + 9., 14., color);
+        }
+    }
+
+    fn draw_pairs(&self) {
+        let color = Color::from_rgba(141, 141, 141, 255);
+        let offset = self.offset as f32;
+        let r = offset / 8.;
+        for constraint in &self.model.rules.constraints {
+            let pair = match constraint {
+                Constraint::Pair(pair) => pair,
+                _ => { continue; }
+            };
+            let (ax, ay) = (pair.a % self.model.width, pair.a / self.model.width);
+            let (bx, by) = (pair.b % self.model.width, pair.b / self.model.width);
+            // middle of the border between the two cells
+            let x = (self.cell_x(ax) + self.cell_x(bx)) / 2. + offset / 2.;
+            let y = (self.cell_y(ay) + self.cell_y(by)) / 2. + offset / 2.;
+            match pair.relation {
+                Relation::Greater => {
+                    // a chevron pointing at the smaller digit
+                    let (dx, dy) = ((bx as f32 - ax as f32) * r, (by as f32 - ay as f32) * r);
+                    draw_line(x - dx - dy, y - dy - dx, x + dx, y + dy, 1.5, color);
+                    draw_line(x - dx + dy, y - dy + dx, x + dx, y + dy, 1.5, color);
+                }
+                Relation::Consecutive => {
+                    draw_circle(x, y, r, WHITE);
+                    draw_circle_lines(x, y, r, 1., color);
+                }
+                Relation::Double => { draw_circle(x, y, r, color); }
+                Relation::NoDot => {}
+            }
+        }
+    }
+
+    /// Connects checked cells with the cell a knight's or king's move away
+    /// that holds the same digit.
+    fn draw_chess_conflicts(&self) {
+        if self.model.no_valid.is_empty() {
+            return;
+        }
+        let color = Color::from_rgba(255, 0, 0, 110);
+        let half = self.offset as f32 / 2.;
+        for [a, b] in self.model.rules.chess_conflicts(&self.model.current_grid()) {
+            let (ax, ay) = (a % self.model.width, a / self.model.width);
+            let (bx, by) = (b % self.model.width, b / self.model.width);
+            if !self.model.no_valid.contains(&[ax, ay]) && !self.model.no_valid.contains(&[bx, by]) {
+                continue;
+            }
+            draw_line(self.cell_x(ax) + half, self.cell_y(ay) + half, self.cell_x(bx) + half, self.cell_y(by) + half, 3., color);
+        }
+    }
+
+    fn draw_number_buttons(&mut self) {
+        if self.model.is_paused {
+            return;
+        }
+        let mut y = self.end_y as f32 + PAD_OFFSET * 1.8;
+        let r = 26.;
+        let circle_x_offset = 13.;
+        let circle_y_offset = 20.;
+        let circle_color = Color::from_rgba(166, 166, 166, 255);
+        let first_x = self.start_x as f32 + r;
+        let mut keys: Vec<(String, KeyCode)> = vec![];
+        for num in 1..=self.model.size as u8 {
+            let label = match self.symbols.is_text() {
+                true => self.symbols.label(num - 1),
+                // wide enough to be pressed, the picture is drawn over it
+                false => "   ".to_owned(),
+            };
+            keys.push((label, self.symbols.key_by_num(num)));
+        }
+        keys.push(("X".to_owned(), KeyCode::Delete));
+        let per_row = if keys.len() <= 10 { 5 } else { 6 };
+        let step = (PAD_OFFSET / 1.7 * 3.).min((GRID_WIDTH as f32 - r * 2.) / (per_row - 1) as f32);
+
+        self.numbers_coord = vec![];
+        for (i, (label, key)) in keys.into_iter().enumerate() {
+            if i > 0 && i % per_row == 0 {
+                y += r * 2.2;
+            }
+            let new_offset = step * (i % per_row) as f32;
+            draw_circle_lines(first_x + new_offset + circle_x_offset, y + circle_y_offset, r, 1.0, circle_color);
+            self.numbers_coord.push(Circle { x: first_x + new_offset + circle_x_offset, y: y + circle_y_offset, r, key });
+            if root_ui().button(Vec2::new(first_x + new_offset, y), label) {
+                self.fill_num(Option::Some(key));
+            }
+            match self.symbols.num_by_key(key) {
+                Some(num) if !self.symbols.is_text() => {
+                    self.symbols.draw_picture(num - 1, first_x + new_offset + circle_x_offset, y + circle_y_offset, r / 2., circle_color);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn draw_hit_buttons(&mut self) {
+        if self.model.is_paused {
+            return;
+        }
+        let y = self.end_y as f32 - PAD_OFFSET / 3.0;
+        let offset = PAD_OFFSET / 1.7;
+        let first_x = self.start_x as f32;
+
+        if root_ui().button(Vec2::new(first_x, y + offset), "Проверить") {
+            self.model.validate();
+        }
+        if root_ui().button(Vec2::new(first_x + offset as f32 * 8., y + offset), "Подсказка") {
+            self.model.hint();
+        }
+    }
+
+    fn draw_zoom_buttons(&mut self) {
+        if self.model.is_paused || !self.can_zoom() {
+            return;
+        }
+        let y = self.end_y as f32 - PAD_OFFSET / 3.0 + PAD_OFFSET / 1.7;
+        if root_ui().button(Vec2::new(self.end_x as f32 - PAD_OFFSET * 2., y), "-") {
+            self.zoom_out();
+        }
+        if root_ui().button(Vec2::new(self.end_x as f32 - PAD_OFFSET, y), "+") {
+            self.zoom_in();
+        }
+    }
+
+    fn draw_pause_button(&mut self) {
+        if self.model.is_finish || self.model.is_lost {
+            return;
+        }
+        let label = match self.model.is_paused {
+            true => ">",
+            false => "II",
+        };
+        if root_ui().button(Vec2::new(self.end_x as f32 - self.offset as f32 / 2., self.start_y - 50.), label) {
+            match self.model.is_paused {
+                true => self.resume(),
+                false => self.model.pause(),
+            }
+        }
+    }
+
+    fn draw_pause_screen(&self, font: Font) {
+        self.draw_form();
+        draw_rectangle(
+            self.start_x as f32,
+            self.start_y,
+            (self.end_x - self.start_x) as f32,
+            self.end_y - self.start_y,
+            Color::from_rgba(248, 248, 248, 255),
+        );
+        let size = measure_text("Пауза", Some(font), self.font_size as u16, 1.0);
+        draw_text_ex("Пауза", (self.start_x + self.end_x) as f32 / 2. - size.width / 2., (self.start_y + self.end_y) / 2., TextParams {
+            font_size: self.font_size as u16,
+            font,
+            color: Color::from_rgba(125, 208, 255, 255),
+            ..Default::default()
+        });
+    }
+
+    fn draw_game_screen(&mut self, font: Font, mut mouse_x: f32, mut mouse_y: f32, is_finish: bool) {
+        if self.model.is_paused {
+            self.draw_pause_screen(font);
+            return;
+        }
+        self.scroll_view();
+        self.draw_form();
+        if is_finish {
+            self.draw_numbers(vec![], font.clone());
+            return;
+        }
+
+        let mut is_left = false;
+        let mut is_right = false;
+        for touch in touches() {
+            match touch.phase {
+                TouchPhase::Started => {
+                    is_left = true;
+                }
+                TouchPhase::Stationary => {
+                    info!("Stationary");
+                }
+                TouchPhase::Moved => {}
+                TouchPhase::Ended => {
+                    is_left = false;
+                    mouse_x = touch.position[0];
+                    mouse_y = touch.position[1];
+                }
+                TouchPhase::Cancelled => {}
+            };
+        }
+
+        if is_mouse_button_down(MouseButton::Left) {
+            is_left = true
+        }
+        if is_mouse_button_down(MouseButton::Left) {
+            is_right = true
+        }
+
+        match self.in_numbers_coord(mouse_x, mouse_y) {
+            None => {}
+            Some(key_kode) => {
+                if is_left {
+                    self.fill_num(Option::Some(key_kode));
+                }
+            }
+        }
+
+        if !self.in_window(mouse_x, mouse_y) {
+            self.draw_numbers(vec![], font.clone());
+            if is_mouse_button_down(MouseButton::Left) {
+                self.model.unselect();
+            }
+            return;
+        }
+        // the editor keeps conflicts on screen while the grid changes
+        if (is_left || is_right) && !self.is_editing() {
+            self.model.no_valid = vec![]
+        }
+
+
+        let (x, y) = self.coord_by_position(mouse_x, mouse_y);
+        if !self.model.is_active(x, y) {
+            self.draw_numbers(vec![], font.clone());
+            return;
+        }
+
+        if is_left || is_right {
+            if is_left {
+                self.model.select(x, y);
+            }
+            self.draw_form();
+            let key = Key { x, y };
+
+            let mut num = self.model.matrix[y][x];
+            if self.model.user_matrix.contains_key(&key) {
+                num = self.model.user_matrix.get(&key).unwrap().clone() - 1
+            }
+
+            if self.model.empties.contains_key(&key) && !self.model.user_matrix.contains_key(&key) {
+                self.draw_numbers(self.model.marked_coord.clone(), font.clone());
+                return;
+            }
+
+            let need_mark = self.coord_by_num(num);
+
+            self.draw_numbers(need_mark, font.clone());
+
+            return;
+        }
+
+
+        self.fill_num(get_last_key_pressed());
+
+        self.draw_numbers(vec![], font.clone());
+    }
+
+    fn draw_marker(&self, x: f32, y: f32) {
+        let size = self.offset as f32 - 6.;
+        draw_texture_ex(*self.textures.get("color_circle").unwrap(), x, y, WHITE, DrawTextureParams {
+            dest_size: Some(vec2(size, size)),
+            ..Default::default()
+        });
+    }
+
+    fn draw_numbers(&self, mut need_mark: Vec<[usize; 2]>, font: Font) {
+        if need_mark.len() == 0 {
+            need_mark = self.model.marked_coord.clone()
+        }
+        let ghost_cells = match &self.ghost {
+            None => vec![],
+            Some(ghost) => ghost.cells_at(self.model.elapsed),
+        };
+        for counter in self.view_y..self.view_y + self.visible {
+            let y = self.cell_y(counter);
+            for i in self.view_x..self.view_x + self.visible {
+                if !self.model.is_active(i, counter) {
+                    continue;
+                }
+                if ghost_cells.contains(&[i, counter]) && !self.model.user_matrix.contains_key(&Key { x: i, y: counter }) {
+                    let x = self.cell_x(i);
+                    draw_rectangle(x + 2., y + 2., self.offset as f32 - 4., self.offset as f32 - 4., Color::from_rgba(235, 246, 255, 255));
+                }
+                let mut val = Some(self.model.matrix[counter][i]);
+                let mut color = Color::from_rgba(100, 100, 100, 255);
+                for coord in &need_mark {
+                    if coord[0] == i && coord[1] == counter {
+                        let x: f32 = self.cell_x(i) + 3.;
+                        let key = Key { x: coord[0], y: coord[1] };
+                        match self.model.empties.get(&key) {
+                            None => { self.draw_marker(x, y + 3.) }
+                            Some(_) => {}
+                        }
+                        match self.model.user_matrix.get(&key) {
+                            None => {}
+                            Some(_) => { self.draw_marker(x, y + 3.) }
+                        }
+                        color = Color::from_rgba(255, 255, 255, 255);
+                        //color = RED;
+                    }
+                }
+                for coord in &self.model.marked_coord {
+                    if coord[0] == i && coord[1] == counter {
+                        let x = self.cell_x(i);
+                        self.draw_marker(x + 3., y + 3.);
+                        // draw_rectangle(self.start_x as f32 + (self.offset * i) as f32, y, self.offset as f32, self.offset as f32, GREEN);
+                    }
+                }
+                for (key, _) in &self.model.empties {
+                    if key.x == i && key.y == counter {
+                        color = Color::from_rgba(125, 208, 255, 255);
+                        val = None;
+                    }
+                }
+                for (key, v) in &self.model.user_matrix {
+                    if key.x == i && key.y == counter {
+                        color = Color::from_rgba(125, 208, 255, 255);
+                        val = Some(v - 1);
+                        for coord in &self.model.marked_coord {
+                            if coord[0] == key.x && coord[1] == key.y {
+                                color = WHITE;
+                            }
+                        }
+                    }
+                }
+                for coord in &self.model.no_valid {
+                    if coord[0] == i && coord[1] == counter {
+                        color = RED;
+                    }
+                }
+                let num = match val {
+                    None => { continue; }
+                    Some(num) => num,
+                };
+                if !self.symbols.is_text() {
+                    let center = self.offset as f32 / 2.0;
+                    self.symbols.draw_picture(num, self.cell_x(i) + center, y + center, self.offset as f32 / 3.5, color);
+                    continue;
+                }
+                let text_start_x: f32 = self.cell_x(i) + self.offset as f32 / 2.0 - self.cell_font_size / 3.5;
+                let text_start_y: f32 = y + self.offset as f32 - self.offset as f32 / 2.0 + self.cell_font_size / 4.0;
+                draw_text_ex(&self.symbols.label(num), text_start_x, text_start_y, TextParams {
+                    font_size: self.cell_font_size as u16,
+                    font,
+                    color,
+                    ..Default::default()
+                });
+            }
+        }
+    }
+
+    fn draw_replay_screen(&mut self, font: Font, mouse_x: f32, mouse_y: f32) {
+        self.scroll_view();
+        self.draw_form();
+        self.draw_numbers(vec![], font);
+
+        let bar_x = self.start_x as f32;
+        let bar_y = self.end_y + PAD_OFFSET;
+        let bar_width = (self.end_x - self.start_x) as f32;
+        let bar_height = 8.;
+        let progress = match &self.replay {
+            None => { return; }
+            Some(replay) => replay.progress() as f32,
+        };
+        draw_rectangle(bar_x, bar_y, bar_width, bar_height, Color::from_rgba(219, 219, 219, 255));
+        draw_rectangle(bar_x, bar_y, bar_width * progress, bar_height, Color::from_rgba(125, 208, 255, 255));
+        draw_circle(bar_x + bar_width * progress, bar_y + bar_height / 2., bar_height, Color::from_rgba(125, 208, 255, 255));
+
+        if !is_mouse_button_down(MouseButton::Left) {
+            return;
+        }
+        if mouse_x < bar_x || mouse_x > bar_x + bar_width {
+            return;
+        }
+        if mouse_y < bar_y - bar_height * 2. || mouse_y > bar_y + bar_height * 3. {
+            return;
+        }
+        match self.replay.as_mut() {
+            None => {}
+            Some(replay) => { replay.seek(((mouse_x - bar_x) / bar_width) as f64); }
+        }
+        self.apply_replay();
+    }
+}
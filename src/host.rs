@@ -0,0 +1,407 @@
+//! Functions the VK page calls through wasm, see static/index.html.
+//!
+//! Every call returns `OK` or one of the negative `ERR_` codes, nothing here
+//! panics. Strings never cross as raw pointers chosen by the page: the page
+//! asks for `input_buffer(len)`, writes UTF-8 bytes there and passes only the
+//! length. Replies are written to an output buffer owned by this module and
+//! read through `output_buffer()`, they stay valid until the next call.
+//!
+//! The other way round, game events go to the page through `emit`.
+
+use crate::game::events::Event;
+use crate::game::notation;
+use crate::game::solver::{Rules, LOAD_BUDGET};
+use crate::game::Difficult;
+use crate::json::{self, Json};
+
+pub const OK: i32 = 0;
+pub const ERR_NOT_INITIALIZED: i32 = -1;
+// the length doesn't fit the input buffer or the bytes aren't UTF-8
+pub const ERR_BAD_STRING: i32 = -2;
+pub const ERR_BAD_PUZZLE: i32 = -3;
+pub const ERR_NOT_UNIQUE: i32 = -4;
+pub const ERR_BAD_LOCALE: i32 = -5;
+// the game hasn't drawn its first frame yet
+pub const ERR_NO_STATE: i32 = -6;
+// not an answer of the leaderboard server's /top or /friends
+pub const ERR_BAD_RANKINGS: i32 = -7;
+// the solver ran out of guesses before it could tell the puzzle is unique
+pub const ERR_TOO_HARD: i32 = -8;
+
+// Longest string the page can pass, vk-bridge answers with the friends
+// leaderboard are the longest ones.
+const MAX_INPUT: usize = 1 << 16;
+const LOCALES: [&str; 2] = ["ru", "en"];
+
+/// What the game shows right now, sent to the page as JSON by `get_state`.
+pub struct State {
+    pub screen: &'static str,
+    pub difficult: Difficult,
+    pub size: usize,
+    // the board in the notation of `game::notation`, givens and the player's digits
+    pub board: String,
+    pub elapsed: f64,
+    pub score: i64,
+    pub mistakes: u8,
+    pub hints: u32,
+    pub checks: u32,
+    pub is_paused: bool,
+    pub is_finish: bool,
+    pub is_lost: bool,
+}
+
+/// One line of the leaderboard server's rankings.
+pub struct Ranked {
+    pub rank: u32,
+    pub user_id: i64,
+    pub elapsed: f64,
+}
+
+/// Rankings of the day the page fetched after a win, see `set_rankings`.
+pub struct Rankings {
+    pub entries: Vec<Ranked>,
+    // the player's own place, also when it's not among `entries`
+    pub me: Option<Ranked>,
+}
+
+struct Context {
+    user_id: i32,
+    app_id: i32,
+    locale: &'static str,
+    pause_requested: bool,
+    resume_requested: bool,
+    puzzle: Option<(usize, Vec<u8>)>,
+    state: Option<State>,
+    rankings: Option<Rankings>,
+    // answers of vk-bridge calls: request id, success and the JSON
+    bridge_responses: Vec<(u32, bool, String)>,
+    input: Vec<u8>,
+    output: Vec<u8>,
+}
+
+static mut MY_CONTEXT: Option<Context> = None;
+
+fn get_context() -> Option<&'static mut Context> {
+    unsafe { (*std::ptr::addr_of_mut!(MY_CONTEXT)).as_mut() }
+}
+
+/// Reads the first `len` bytes the page wrote to the input buffer.
+fn read_input(ctx: &Context, len: usize) -> Result<String, i32> {
+    if len > ctx.input.len() {
+        return Err(ERR_BAD_STRING);
+    }
+    return match std::str::from_utf8(&ctx.input[..len]) {
+        Ok(text) => Ok(text.to_string()),
+        Err(_) => Err(ERR_BAD_STRING),
+    };
+}
+
+/// Creates the context, calling it again keeps the current one.
+#[no_mangle]
+pub extern "C" fn init_webassembly() -> i32 {
+    if get_context().is_none() {
+        unsafe {
+            MY_CONTEXT = Some(Context {
+                user_id: 0,
+                app_id: 0,
+                locale: LOCALES[0],
+                pause_requested: false,
+                resume_requested: false,
+                puzzle: None,
+                state: None,
+                rankings: None,
+                bridge_responses: vec![],
+                input: vec![],
+                output: vec![],
+            });
+        }
+    }
+    return OK;
+}
+
+#[no_mangle]
+pub extern "C" fn set_user_id(user_id: i32) -> i32 {
+    match get_context() {
+        None => ERR_NOT_INITIALIZED,
+        Some(ctx) => {
+            ctx.user_id = user_id;
+            OK
+        }
+    }
+}
+
+/// The `vk_app_id` launch parameter, needed for user tokens.
+#[no_mangle]
+pub extern "C" fn set_app_id(app_id: i32) -> i32 {
+    match get_context() {
+        None => ERR_NOT_INITIALIZED,
+        Some(ctx) => {
+            ctx.app_id = app_id;
+            OK
+        }
+    }
+}
+
+pub fn app_id() -> i32 {
+    return get_context().map(|ctx| ctx.app_id).unwrap_or(0);
+}
+
+/// 0 until the page passes the VK user.
+pub fn user_id() -> i32 {
+    return get_context().map(|ctx| ctx.user_id).unwrap_or(0);
+}
+
+/// The answer to the vk-bridge call `id` from the input buffer, `ok` is 0
+/// when the call failed and the JSON is its error.
+#[no_mangle]
+pub extern "C" fn vk_bridge_response(id: u32, ok: i32, len: usize) -> i32 {
+    let ctx = match get_context() {
+        None => { return ERR_NOT_INITIALIZED; }
+        Some(ctx) => ctx,
+    };
+    let text = match read_input(ctx, len) {
+        Err(code) => { return code; }
+        Ok(text) => text,
+    };
+    ctx.bridge_responses.push((id, ok != 0, text));
+    return OK;
+}
+
+/// Answers that came since the previous call, see `vk::PageBridge`.
+pub fn take_bridge_responses() -> Vec<(u32, bool, String)> {
+    match get_context() {
+        None => vec![],
+        Some(ctx) => std::mem::take(&mut ctx.bridge_responses),
+    }
+}
+
+/// Room for `len` bytes the page writes before calling a function that
+/// takes a string, null when `len` is too big or before init.
+#[no_mangle]
+pub extern "C" fn input_buffer(len: usize) -> *mut u8 {
+    match get_context() {
+        Some(ctx) if len <= MAX_INPUT => {
+            ctx.input = vec![0; len];
+            ctx.input.as_mut_ptr()
+        }
+        _ => std::ptr::null_mut(),
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn output_buffer() -> *const u8 {
+    match get_context() {
+        None => std::ptr::null(),
+        Some(ctx) => ctx.output.as_ptr(),
+    }
+}
+
+/// Starts a classic puzzle from the input buffer, one line as sudoku-gen
+/// writes it. The puzzle must have exactly one solution, found within
+/// `LOAD_BUDGET` guesses.
+#[no_mangle]
+pub extern "C" fn load_puzzle(len: usize) -> i32 {
+    let ctx = match get_context() {
+        None => { return ERR_NOT_INITIALIZED; }
+        Some(ctx) => ctx,
+    };
+    let line = match read_input(ctx, len) {
+        Err(code) => { return code; }
+        Ok(line) => line,
+    };
+    let (size, grid) = match notation::from_line(&line) {
+        None => { return ERR_BAD_PUZZLE; }
+        Some(puzzle) => puzzle,
+    };
+    let rules = Rules::classic(size);
+    if !rules.conflicts(&grid).is_empty() {
+        return ERR_BAD_PUZZLE;
+    }
+    match rules.try_count_solutions(&grid, 2, LOAD_BUDGET) {
+        None => { return ERR_TOO_HARD; }
+        Some(0) => { return ERR_BAD_PUZZLE; }
+        Some(1) => {}
+        Some(_) => { return ERR_NOT_UNIQUE; }
+    }
+    ctx.puzzle = Some((size, grid));
+    return OK;
+}
+
+/// Rankings from the input buffer, the JSON the leaderboard server answers
+/// /top and /friends with. The game shows them on the win screen.
+#[no_mangle]
+pub extern "C" fn set_rankings(len: usize) -> i32 {
+    let ctx = match get_context() {
+        None => { return ERR_NOT_INITIALIZED; }
+        Some(ctx) => ctx,
+    };
+    let text = match read_input(ctx, len) {
+        Err(code) => { return code; }
+        Ok(text) => text,
+    };
+    match json::parse(&text).as_ref().and_then(parse_rankings) {
+        None => ERR_BAD_RANKINGS,
+        Some(rankings) => {
+            ctx.rankings = Some(rankings);
+            OK
+        }
+    }
+}
+
+fn parse_ranked(json: &Json) -> Option<Ranked> {
+    return Some(Ranked {
+        rank: json.get("rank")?.as_i64()? as u32,
+        user_id: json.get("user_id")?.as_i64()?,
+        elapsed: json.get("elapsed")?.as_f64()?,
+    });
+}
+
+fn parse_rankings(json: &Json) -> Option<Rankings> {
+    let mut entries = vec![];
+    for entry in json.get("entries")?.as_array()? {
+        entries.push(parse_ranked(entry)?);
+    }
+    let me = match json.get("me") {
+        None | Some(Json::Null) => None,
+        Some(me) => Some(parse_ranked(me)?),
+    };
+    return Some(Rankings { entries, me });
+}
+
+/// Rankings passed to `set_rankings` that the game hasn't shown yet.
+pub fn take_rankings() -> Option<Rankings> {
+    return get_context()?.rankings.take();
+}
+
+/// Writes the state JSON to the output buffer and returns its length in bytes.
+#[no_mangle]
+pub extern "C" fn get_state() -> i32 {
+    let ctx = match get_context() {
+        None => { return ERR_NOT_INITIALIZED; }
+        Some(ctx) => ctx,
+    };
+    let json = match &ctx.state {
+        None => { return ERR_NO_STATE; }
+        Some(state) => state_json(state, ctx.user_id, ctx.locale),
+    };
+    ctx.output = json.into_bytes();
+    return ctx.output.len() as i32;
+}
+
+/// Sets the page language from the input buffer, "ru" or "en". It is only
+/// reported back in the state so far, the window texts are Russian.
+#[no_mangle]
+pub extern "C" fn set_locale(len: usize) -> i32 {
+    let ctx = match get_context() {
+        None => { return ERR_NOT_INITIALIZED; }
+        Some(ctx) => ctx,
+    };
+    let locale = match read_input(ctx, len) {
+        Err(code) => { return code; }
+        Ok(locale) => locale,
+    };
+    match LOCALES.iter().find(|l| **l == locale.to_lowercase()) {
+        None => ERR_BAD_LOCALE,
+        Some(l) => {
+            ctx.locale = *l;
+            OK
+        }
+    }
+}
+
+/// Called by the page when the tab is hidden or the VK app is minimised.
+#[no_mangle]
+pub extern "C" fn pause_game() -> i32 {
+    match get_context() {
+        None => ERR_NOT_INITIALIZED,
+        Some(ctx) => {
+            ctx.pause_requested = true;
+            ctx.resume_requested = false;
+            OK
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn resume_game() -> i32 {
+    match get_context() {
+        None => ERR_NOT_INITIALIZED,
+        Some(ctx) => {
+            ctx.resume_requested = true;
+            ctx.pause_requested = false;
+            OK
+        }
+    }
+}
+
+/// True once after every `pause_game` call.
+pub fn take_pause_request() -> bool {
+    match get_context() {
+        None => false,
+        Some(ctx) => std::mem::replace(&mut ctx.pause_requested, false),
+    }
+}
+
+/// True once after every `resume_game` call.
+pub fn take_resume_request() -> bool {
+    match get_context() {
+        None => false,
+        Some(ctx) => std::mem::replace(&mut ctx.resume_requested, false),
+    }
+}
+
+/// The puzzle passed to `load_puzzle` that the game hasn't started yet.
+pub fn take_puzzle() -> Option<(usize, Vec<u8>)> {
+    return get_context()?.puzzle.take();
+}
+
+/// Called by the game every frame, the page reads it with `get_state`.
+pub fn set_state(state: State) {
+    if let Some(ctx) = get_context() {
+        ctx.state = Some(state);
+    }
+}
+
+fn state_json(state: &State, user_id: i32, locale: &str) -> String {
+    let fields = vec![
+        format!("\"user_id\":{}", user_id),
+        format!("\"locale\":\"{}\"", locale),
+        format!("\"screen\":\"{}\"", state.screen),
+        format!("\"difficult\":\"{}\"", state.difficult.name()),
+        format!("\"size\":{}", state.size),
+        format!("\"board\":\"{}\"", state.board),
+        format!("\"elapsed\":{:.1}", state.elapsed),
+        format!("\"score\":{}", state.score),
+        format!("\"mistakes\":{}", state.mistakes),
+        format!("\"hints\":{}", state.hints),
+        format!("\"checks\":{}", state.checks),
+        format!("\"is_paused\":{}", state.is_paused),
+        format!("\"is_finish\":{}", state.is_finish),
+        format!("\"is_lost\":{}", state.is_lost),
+    ];
+    return format!("{{{}}}", fields.join(","));
+}
+
+#[cfg(target_arch = "wasm32")]
+extern "C" {
+    // registered by the sudoku_events plugin in static/script.js
+    fn sudoku_event(json: *const u8, len: usize);
+}
+
+// desktop builds have no page to tell
+#[cfg(not(target_arch = "wasm32"))]
+unsafe fn sudoku_event(_json: *const u8, _len: usize) {}
+
+/// Version of the sudoku_events plugin the page must have, 0.1.0.
+#[no_mangle]
+pub extern "C" fn sudoku_events_crate_version() -> u32 {
+    return 1 << 16;
+}
+
+/// Hands `event` to the page as JSON, it arrives as a "sudoku" DOM event.
+pub fn emit(event: &Event) {
+    let json = event.to_json();
+    unsafe {
+        sudoku_event(json.as_ptr(), json.len());
+    }
+}
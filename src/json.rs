@@ -0,0 +1,277 @@
+//! Just enough JSON for the answers of VK bridge and the leaderboard server.
+//! Objects keep their keys in order, numbers are f64.
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// Field of an object, None for a missing key or not an object.
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(fields) => fields.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Json::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        return self.as_f64().map(|n| n as i64);
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Json::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&Vec<Json>> {
+        match self {
+            Json::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+}
+
+/// `text` as a JSON string literal with quotes.
+pub fn quote(text: &str) -> String {
+    let mut out = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    return out;
+}
+
+/// None when `text` isn't one complete JSON value.
+pub fn parse(text: &str) -> Option<Json> {
+    let mut parser = Parser { chars: text.chars().collect(), pos: 0, depth: 0 };
+    let value = parser.value()?;
+    parser.skip_spaces();
+    if parser.pos != parser.chars.len() {
+        return None;
+    }
+    return Some(value);
+}
+
+// Arrays and objects nest at most this deep.
+const MAX_DEPTH: usize = 32;
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    // arrays and objects the parser is inside of
+    depth: usize,
+}
+
+impl Parser {
+    fn skip_spaces(&mut self) {
+        while self.pos < self.chars.len() && self.chars[self.pos].is_whitespace() {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_spaces();
+        return self.chars.get(self.pos).copied();
+    }
+
+    fn expect(&mut self, c: char) -> Option<()> {
+        if self.peek()? != c {
+            return None;
+        }
+        self.pos += 1;
+        return Some(());
+    }
+
+    fn word(&mut self, word: &str, value: Json) -> Option<Json> {
+        for c in word.chars() {
+            if self.chars.get(self.pos) != Some(&c) {
+                return None;
+            }
+            self.pos += 1;
+        }
+        return Some(value);
+    }
+
+    fn value(&mut self) -> Option<Json> {
+        match self.peek()? {
+            'n' => self.word("null", Json::Null),
+            't' => self.word("true", Json::Bool(true)),
+            'f' => self.word("false", Json::Bool(false)),
+            '"' => Some(Json::String(self.string()?)),
+            '[' | '{' => {
+                // the server parses bodies from anyone, deep nesting must not
+                // overflow the stack
+                if self.depth == MAX_DEPTH {
+                    return None;
+                }
+                self.depth += 1;
+                let value = match self.peek()? {
+                    '[' => self.array(),
+                    _ => self.object(),
+                };
+                self.depth -= 1;
+                return value;
+            }
+            _ => self.number(),
+        }
+    }
+
+    fn array(&mut self) -> Option<Json> {
+        self.pos += 1;
+        let mut items = vec![];
+        if self.peek()? == ']' {
+            self.pos += 1;
+            return Some(Json::Array(items));
+        }
+        loop {
+            items.push(self.value()?);
+            match self.peek()? {
+                ',' => { self.pos += 1; }
+                ']' => {
+                    self.pos += 1;
+                    return Some(Json::Array(items));
+                }
+                _ => { return None; }
+            }
+        }
+    }
+
+    fn object(&mut self) -> Option<Json> {
+        self.pos += 1;
+        let mut fields = vec![];
+        if self.peek()? == '}' {
+            self.pos += 1;
+            return Some(Json::Object(fields));
+        }
+        loop {
+            self.peek()?;
+            let key = self.string()?;
+            self.expect(':')?;
+            fields.push((key, self.value()?));
+            match self.peek()? {
+                ',' => { self.pos += 1; }
+                '}' => {
+                    self.pos += 1;
+                    return Some(Json::Object(fields));
+                }
+                _ => { return None; }
+            }
+        }
+    }
+
+    fn number(&mut self) -> Option<Json> {
+        let start = self.pos;
+        while self.pos < self.chars.len() && "+-0123456789.eE".contains(self.chars[self.pos]) {
+            self.pos += 1;
+        }
+        let text: String = self.chars[start..self.pos].iter().collect();
+        let number: f64 = text.parse().ok()?;
+        // 1e999 parses as infinity, which no JSON number is
+        if !number.is_finite() {
+            return None;
+        }
+        return Some(Json::Number(number));
+    }
+
+    fn string(&mut self) -> Option<String> {
+        if self.chars.get(self.pos) != Some(&'"') {
+            return None;
+        }
+        self.pos += 1;
+        let mut out = String::new();
+        loop {
+            let c = *self.chars.get(self.pos)?;
+            self.pos += 1;
+            match c {
+                '"' => { return Some(out); }
+                '\\' => {
+                    let escaped = *self.chars.get(self.pos)?;
+                    self.pos += 1;
+                    match escaped {
+                        'n' => out.push('\n'),
+                        'r' => out.push('\r'),
+                        't' => out.push('\t'),
+                        'b' => out.push('\u{8}'),
+                        'f' => out.push('\u{c}'),
+                        'u' => {
+                            let hex: String = self.chars.get(self.pos..self.pos + 4)?.iter().collect();
+                            self.pos += 4;
+                            let mut code = u32::from_str_radix(&hex, 16).ok()?;
+                            // a surrogate pair is two \u escapes in a row
+                            if (0xD800..0xDC00).contains(&code) && self.chars.get(self.pos..self.pos + 2) == Some(&['\\', 'u']) {
+                                let low: String = self.chars.get(self.pos + 2..self.pos + 6)?.iter().collect();
+                                let low = u32::from_str_radix(&low, 16).ok()?;
+                                self.pos += 6;
+                                code = 0x10000 + ((code - 0xD800) << 10) + (low.wrapping_sub(0xDC00) & 0x3FF);
+                            }
+                            out.push(std::char::from_u32(code).unwrap_or('\u{FFFD}'));
+                        }
+                        c => out.push(c),
+                    }
+                }
+                c => out.push(c),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nesting_is_limited() {
+        let nested = |depth: usize| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+        assert!(parse(&nested(MAX_DEPTH)).is_some());
+        assert!(parse(&nested(MAX_DEPTH + 1)).is_none());
+        assert!(parse(&"[{\"a\":".repeat(200_000)).is_none());
+    }
+
+    #[test]
+    fn values() {
+        let value = parse(" {\"a\": [1, -2.5e1, true, null], \"b\": \"\\u0416\\n\"} ").unwrap();
+        let items = value.get("a").and_then(|a| a.as_array()).unwrap();
+        assert_eq!(items[1].as_f64(), Some(-25.));
+        assert_eq!(items[2].as_bool(), Some(true));
+        assert_eq!(value.get("b").and_then(|b| b.as_str()), Some("Ж\n"));
+        assert!(parse("[1,]").is_none());
+        assert!(parse("{} x").is_none());
+    }
+
+    #[test]
+    fn numbers_are_finite() {
+        assert_eq!(parse("1e308").and_then(|n| n.as_f64()), Some(1e308));
+        assert!(parse("1e999").is_none());
+        assert!(parse("{\"elapsed\": -1e999}").is_none());
+    }
+}
@@ -0,0 +1,12 @@
+//! Sudoku engine: rules and solver, generator, difficulty rating, text
+//! notation, replays and statistics. The macroquad game window needs the `ui`
+//! feature and the functions the VK page calls need `vk`, both are on by default.
+
+pub mod game;
+#[cfg(feature = "vk")]
+pub mod host;
+pub mod json;
+#[cfg(feature = "ui")]
+pub mod skins;
+#[cfg(feature = "vk")]
+pub mod vk;
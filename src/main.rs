@@ -1,37 +1,86 @@
 use macroquad::prelude::*;
-use macroquad::ui::{root_ui, widgets};
+use macroquad::ui::{hash, root_ui, widgets};
 
-mod game;
-mod skins;
+use sudoku_vk::{game, host, skins, vk};
+use sudoku_vk::game::events::Event;
+use sudoku_vk::game::UI;
 
-use crate::game::UI;
+const DIFFICULTS: [game::Difficult; 4] = [
+    game::Difficult::SuperEasy,
+    game::Difficult::Easy,
+    game::Difficult::Medium,
+    game::Difficult::Hard,
+];
 
-struct Context {
-    user_id: i32,
+fn game_state(g: &game::Game) -> host::State {
+    let screen = match g.current_screen {
+        game::Screens::Start => "start",
+        game::Screens::Game => "game",
+        game::Screens::Replay => "replay",
+        game::Screens::Editor => "editor",
+    };
+    return host::State {
+        screen,
+        difficult: g.model.current_difficult,
+        size: g.model.size,
+        board: g.model.get_board(),
+        elapsed: g.model.elapsed,
+        score: g.model.score,
+        mistakes: g.model.mistakes,
+        hints: g.model.hints_used,
+        checks: g.model.checks_used,
+        is_paused: g.model.is_paused,
+        is_finish: g.model.is_finish,
+        is_lost: g.model.is_lost,
+    };
 }
 
-#[no_mangle]
-static mut MY_CONTEXT: Option<Context> = None;
-
-fn get_context() -> &'static mut Context {
-    unsafe { MY_CONTEXT.as_mut().unwrap_or_else(|| panic!("blabla")) }
+/// The top of the day's rankings under the win window, top left at `x`, `y`.
+fn draw_rankings(rankings: &host::Rankings, x: f32, y: f32, width: f32, font: Font, font_size: u16) {
+    let me = rankings.me.as_ref().map(|m| m.user_id).unwrap_or(0);
+    let mut lines: Vec<(String, bool)> = rankings.entries.iter()
+        .take(5)
+        .map(|r| (r.rank, r.user_id, r.elapsed))
+        .chain(rankings.me.iter().filter(|m| m.rank > 5).map(|m| (m.rank, m.user_id, m.elapsed)))
+        .map(|(rank, user_id, elapsed)| {
+            let name = if user_id == me { "Вы".to_string() } else { format!("id{}", user_id) };
+            let time = format!("{:02}:{:02}", elapsed as i64 / 60, elapsed as i64 % 60);
+            (format!("{}. {} {}", rank, name, time), user_id == me)
+        })
+        .collect();
+    lines.insert(0, ("Рейтинг дня".to_string(), false));
+    draw_lines(&lines, x, y, width, font, font_size);
 }
 
-
-#[no_mangle]
-fn set_user_id(user_id: i32) {
-    let ctx = get_context();
-    ctx.user_id = user_id;
+/// The friends' VK leaderboard in place of the day's rankings.
+fn draw_friends(entries: &[vk::Entry], x: f32, y: f32, width: f32, font: Font, font_size: u16) {
+    let me = host::user_id() as i64;
+    let mut lines: Vec<(String, bool)> = entries.iter()
+        .take(5)
+        .enumerate()
+        .map(|(i, e)| (format!("{}. {} {}", i + 1, e.name, e.score), e.user_id == me))
+        .collect();
+    lines.insert(0, ("Друзья".to_string(), false));
+    draw_lines(&lines, x, y, width, font, font_size);
 }
 
-#[no_mangle]
-fn init_webassembly() {
-    unsafe {
-        MY_CONTEXT = Some(Context { user_id: 0 });
+/// A framed list, lines with true are the player's and highlighted.
+fn draw_lines(lines: &[(String, bool)], x: f32, y: f32, width: f32, font: Font, font_size: u16) {
+    let line_height = font_size as f32 + 6.;
+    let height = line_height * lines.len() as f32 + 16.;
+    draw_rectangle(x, y, width, height, Color::from_rgba(248, 248, 248, 255));
+    draw_rectangle_lines(x, y, width, height, 2., Color::from_rgba(125, 208, 255, 255));
+    for (i, (line, is_me)) in lines.iter().enumerate() {
+        let color = if *is_me { Color::from_rgba(125, 208, 255, 255) } else { Color::from_rgba(141, 141, 141, 255) };
+        draw_text_ex(line, x + 16., y + 8. + line_height * (i + 1) as f32, TextParams {
+            font_size,
+            font,
+            color,
+            ..Default::default()
+        });
     }
 }
 
-
 #[macroquad::main("Sudoku")]
 async fn main() {
     let mut g = game::Game::new(screen_height(), screen_width(), game::Difficult::SuperEasy).await;
@@ -46,10 +95,52 @@ async fn main() {
     let current_skin = skins::DefaultSkin::default(&root_ui());
     let center_x = screen_width() / 2.0;
     let center_y = screen_height() / 2.0;
+    let mut vk = vk::Vk::new(vk::PageBridge::new());
+    let best_keys: Vec<String> = DIFFICULTS.iter().map(|d| format!("best_{}", d.name())).collect();
+    vk.load_progress(&best_keys.iter().map(|k| k.as_str()).collect::<Vec<&str>>());
+    // the page fetches them from the leaderboard server after every win
+    let mut rankings: Option<host::Rankings> = None;
+    // the friends' leaderboard replaces the rankings once asked for
+    let mut show_friends = false;
 
     loop {
         clear_background(WHITE);
 
+        if let Some((size, puzzle)) = host::take_puzzle() {
+            g.load_puzzle(size, &puzzle);
+        }
+        // taken every frame, so a pause from the menu doesn't carry over to the next game
+        let is_pause_requested = host::take_pause_request();
+        let is_resume_requested = host::take_resume_request();
+        if let game::Screens::Game = g.current_screen {
+            if is_pause_requested {
+                g.model.pause();
+            }
+            if is_resume_requested && g.model.is_paused {
+                g.resume();
+            }
+        }
+        host::set_state(game_state(&g));
+        vk.update();
+        if let Some(r) = host::take_rankings() {
+            rankings = Some(r);
+        }
+        for event in g.model.take_events() {
+            host::emit(&event);
+            if let Event::Started { .. } = event {
+                rankings = None;
+                show_friends = false;
+            }
+            if let Event::Won { difficult, score, .. } = event {
+                // best scores live in VK Storage, so they follow the player between devices
+                let key = format!("best_{}", difficult.name());
+                let best = vk.progress.get(&key).and_then(|v| v.parse::<i64>().ok()).unwrap_or(0);
+                if score > best {
+                    vk.save_progress(&key, &score.to_string());
+                }
+            }
+        }
+
         let (mouse_x, mouse_y) = mouse_position();
         // debug!("{} {} {} {}", mouse_x, mouse_y, screen_width(), screen_height());
         match g.current_screen {
@@ -64,29 +155,29 @@ async fn main() {
 
                 root_ui().push_skin(&current_skin.right_ar_skin);
                 if root_ui().button(vec2(center_x + logo.width(), center_y + 114.), "   ") {
-                    match g.current_difficult {
-                        game::Difficult::SuperEasy => { g.current_difficult = game::Difficult::Easy }
-                        game::Difficult::Easy => { g.current_difficult = game::Difficult::Medium }
-                        game::Difficult::Medium => { g.current_difficult = game::Difficult::Hard }
-                        game::Difficult::Hard => { g.current_difficult = game::Difficult::SuperEasy }
+                    match g.model.current_difficult {
+                        game::Difficult::SuperEasy => { g.model.current_difficult = game::Difficult::Easy }
+                        game::Difficult::Easy => { g.model.current_difficult = game::Difficult::Medium }
+                        game::Difficult::Medium => { g.model.current_difficult = game::Difficult::Hard }
+                        game::Difficult::Hard => { g.model.current_difficult = game::Difficult::SuperEasy }
                     }
                 }
 
                 root_ui().pop_skin();
                 root_ui().push_skin(&current_skin.left_ar_skin);
                 if root_ui().button(vec2(center_x - logo.width(), center_y + 114.), "   ") {
-                    match g.current_difficult {
-                        game::Difficult::SuperEasy => { g.current_difficult = game::Difficult::Hard }
-                        game::Difficult::Easy => { g.current_difficult = game::Difficult::SuperEasy }
-                        game::Difficult::Medium => { g.current_difficult = game::Difficult::Easy }
-                        game::Difficult::Hard => { g.current_difficult = game::Difficult::Medium }
+                    match g.model.current_difficult {
+                        game::Difficult::SuperEasy => { g.model.current_difficult = game::Difficult::Hard }
+                        game::Difficult::Easy => { g.model.current_difficult = game::Difficult::SuperEasy }
+                        game::Difficult::Medium => { g.model.current_difficult = game::Difficult::Easy }
+                        game::Difficult::Hard => { g.model.current_difficult = game::Difficult::Medium }
                     }
                 }
 
                 root_ui().pop_skin();
                 root_ui().push_skin(&current_skin.start_skin);
                 let level_name: String;
-                match g.current_difficult {
+                match g.model.current_difficult {
                     game::Difficult::SuperEasy => { level_name = "Начинающий".to_owned() }
                     game::Difficult::Easy => { level_name = "Легко".to_owned() }
                     game::Difficult::Medium => { level_name = "Средне".to_owned() }
@@ -95,21 +186,85 @@ async fn main() {
                 let offset = level_name.chars().count() as f32 * (g.font_size - 12.);
                 root_ui().label(vec2(center_x - offset / 2., center_y + 110.), &level_name);
                 if root_ui().button(vec2(center_x - button.width() / 2., center_y + 200.), "Новая Игра") {
-                    g.regenerate();
-                    g.current_screen = game::Screens::Game;
+                    if g.regenerate() {
+                        g.current_screen = game::Screens::Game;
+                    }
+                }
+                root_ui().pop_skin();
+                root_ui().push_skin(&current_skin.big_button_skin);
+                let mistakes_name = match g.model.mistake_limit {
+                    None => "Без ограничения ошибок".to_owned(),
+                    Some(limit) => format!("Не больше {} ошибок", limit),
+                };
+                if root_ui().button(vec2(center_x - button.width() / 2., center_y + 270.), mistakes_name) {
+                    g.model.switch_mistake_limit();
+                }
+                if root_ui().button(vec2(center_x + 40., center_y + 270.), "Задача дня") {
+                    if g.play_daily() {
+                        g.current_screen = game::Screens::Game;
+                    }
+                }
+                let variant_name = match g.model.current_variant {
+                    game::Variant::Classic => "Классика",
+                    game::Variant::Diagonal => "Диагональ",
+                    game::Variant::Killer => "Киллер",
+                    game::Variant::Jigsaw => "Пазл",
+                    game::Variant::Samurai => "Самурай",
+                    game::Variant::Windoku => "Виндоку",
+                    game::Variant::Inequality => "Больше-меньше",
+                    game::Variant::Kropki => "Кропки",
+                };
+                if root_ui().button(vec2(center_x - button.width() / 2., center_y + 330.), variant_name) {
+                    g.switch_variant();
+                }
+                if root_ui().button(vec2(center_x + 40., center_y + 330.), format!("{}x{}", g.model.size, g.model.size)) {
+                    g.switch_size();
+                }
+                let chess_name = match g.model.chess {
+                    game::Chess::Off => "Без шахматных правил",
+                    game::Chess::Knight => "Антиконь",
+                    game::Chess::King => "Антикороль",
+                    game::Chess::Both => "Антиконь и антикороль",
+                };
+                if root_ui().button(vec2(center_x - button.width() / 2., center_y + 390.), chess_name) {
+                    g.switch_chess();
+                }
+                let symbols_name = match g.symbols {
+                    game::symbols::SymbolSet::Digits => "Цифры",
+                    game::symbols::SymbolSet::Word => "Буквы",
+                    game::symbols::SymbolSet::Colors => "Цвета",
+                    game::symbols::SymbolSet::Shapes => "Фигуры",
+                };
+                if root_ui().button(vec2(center_x - button.width() / 2., center_y + 450.), symbols_name) {
+                    g.switch_symbols();
+                }
+                if root_ui().button(vec2(center_x + 40., center_y + 450.), "Редактор") {
+                    g.open_editor();
                 }
             }
             game::Screens::Game => {
+                g.tick();
                 root_ui().push_skin(&current_skin.numbers_button_skin);
                 g.draw_number_buttons();
                 root_ui().pop_skin();
                 root_ui().push_skin(&current_skin.big_button_skin);
                 g.draw_hit_buttons();
+                g.draw_zoom_buttons();
                 root_ui().pop_skin();
                 root_ui().push_skin(&current_skin.game_skin);
-                g.draw_game_screen(font, mouse_x, mouse_y, g.is_finish);
+                g.draw_game_screen(font, mouse_x, mouse_y, g.model.is_finish || g.model.is_lost);
 
-                if g.is_win() {
+                if g.model.is_paused {
+                    root_ui().pop_skin();
+                    root_ui().push_skin(&current_skin.start_skin);
+                    if root_ui().button(vec2(center_x - button.width() / 2., center_y + 40.), "Продолжить") {
+                        g.resume();
+                    }
+                    root_ui().pop_skin();
+                    root_ui().push_skin(&current_skin.game_skin);
+                }
+
+                if g.model.is_win() {
                     let w_x_size = 400.;
                     let w_y_size = 368.;
                     widgets::Window::new(1, vec2(center_x - w_x_size / 2., center_y - w_y_size / 2.), vec2(w_x_size, w_y_size))
@@ -122,23 +277,211 @@ async fn main() {
                                 w_y_size - 2.,
                             );
                             if ui.button(Vec2::new(w_x_size - 100., w_y_size - 50.), "На главную") {
-                                g.current_screen = game::Screens::Start;
+                                g.leave();
+                            }
+                            if ui.button(Vec2::new(20., w_y_size - 50.), "Повтор") {
+                                if g.start_replay() {
+                                    g.current_screen = game::Screens::Replay;
+                                }
+                            }
+                            if ui.button(Vec2::new(w_x_size / 2. - 50., w_y_size - 50.), "Ещё раз") {
+                                g.restart();
+                            }
+                            if ui.button(Vec2::new(20., w_y_size - 100.), "Поделиться") {
+                                vk.share_to_wall(g.model.current_difficult, &g.model.get_duration(), g.model.score);
+                            }
+                            if ui.button(Vec2::new(w_x_size / 2. - 50., w_y_size - 100.), "В историю") {
+                                vk.share_to_story(host::app_id());
+                            }
+                            if ui.button(Vec2::new(w_x_size - 100., w_y_size - 100.), "Друзья") {
+                                vk.submit_score(g.model.score);
+                                vk.fetch_leaderboard(host::app_id());
+                                show_friends = true;
                             }
                         });
+                    let y = center_y + w_y_size / 2. + 10.;
+                    match (&vk.leaderboard, &rankings) {
+                        (Some(friends), _) if show_friends => draw_friends(friends, center_x - w_x_size / 2., y, w_x_size, font, 20),
+                        (_, Some(rankings)) => draw_rankings(rankings, center_x - w_x_size / 2., y, w_x_size, font, 20),
+                        _ => {}
+                    }
                 }
-                let size = measure_text(&g.get_duration(), Some(font), g.font_size as u16, 1.0);
-                draw_text_ex(&g.get_duration(), center_x - size.width / 2., g.start_y - 20., TextParams {
+                if g.model.is_lost {
+                    let w_x_size = 400.;
+                    let w_y_size = 260.;
+                    let w_x = center_x - w_x_size / 2.;
+                    let w_y = center_y - w_y_size / 2.;
+                    draw_rectangle(w_x, w_y, w_x_size, w_y_size, Color::from_rgba(248, 248, 248, 255));
+                    draw_rectangle_lines(w_x, w_y, w_x_size, w_y_size, 2., Color::from_rgba(125, 208, 255, 255));
+                    let size = measure_text("Игра окончена", Some(font), g.font_size as u16, 1.0);
+                    draw_text_ex("Игра окончена", center_x - size.width / 2., w_y + 60., TextParams {
+                        font_size: g.font_size as u16,
+                        font,
+                        color: Color::from_rgba(141, 141, 141, 255),
+                        ..Default::default()
+                    });
+                    root_ui().pop_skin();
+                    root_ui().push_skin(&current_skin.start_skin);
+                    if root_ui().button(vec2(center_x - button.width() / 2., w_y + 100.), "Повтор") {
+                        if g.start_replay() {
+                            g.current_screen = game::Screens::Replay;
+                        }
+                    }
+                    if root_ui().button(vec2(center_x - button.width() / 2., w_y + w_y_size - 80.), "На главную") {
+                        g.leave();
+                    }
+                    root_ui().pop_skin();
+                    root_ui().push_skin(&current_skin.game_skin);
+                }
+                match g.model.get_mistakes() {
+                    None => {}
+                    Some(mistakes) => {
+                        let size = measure_text(&mistakes, Some(font), (g.font_size / 1.5) as u16, 1.0);
+                        draw_text_ex(&mistakes, center_x - size.width / 2., g.start_y - 50., TextParams {
+                            font_size: (g.font_size / 1.5) as u16,
+                            font,
+                            color: Color::from_rgba(141, 141, 141, 255),
+                            ..Default::default()
+                        });
+                    }
+                }
+                let score = match g.model.is_finish {
+                    true => match g.model.get_best_score() {
+                        None => g.model.get_score(),
+                        Some(best) => format!("{} / {}", g.model.get_score(), best),
+                    },
+                    false => g.model.get_score(),
+                };
+                let size = measure_text(&score, Some(font), (g.font_size / 1.5) as u16, 1.0);
+                draw_text_ex(&score, g.end_x as f32 - size.width, g.start_y - 8., TextParams {
+                    font_size: (g.font_size / 1.5) as u16,
+                    font,
+                    color: Color::from_rgba(141, 141, 141, 255),
+                    ..Default::default()
+                });
+                let size = measure_text(&g.model.get_duration(), Some(font), g.font_size as u16, 1.0);
+                draw_text_ex(&g.model.get_duration(), center_x - size.width / 2., g.start_y - 20., TextParams {
                     font_size: g.font_size as u16,
                     font,
                     color: Color::from_rgba(141, 141, 141, 255),
                     ..Default::default()
                 });
+                match g.get_ghost_delta() {
+                    None => {}
+                    Some((delta, is_ahead)) => {
+                        let color = match is_ahead {
+                            true => Color::from_rgba(80, 180, 80, 255),
+                            false => Color::from_rgba(230, 90, 90, 255),
+                        };
+                        draw_text_ex(&delta, center_x + size.width / 2. + 10., g.start_y - 20., TextParams {
+                            font_size: (g.font_size / 1.5) as u16,
+                            font,
+                            color,
+                            ..Default::default()
+                        });
+                    }
+                }
 
+                root_ui().pop_skin();
+                root_ui().push_skin(&current_skin.button_arrow_skin);
+                if root_ui().button(Vec2::new(g.start_x as f32, g.start_y - 50.), "J") {
+                    g.leave();
+                }
+                root_ui().pop_skin();
+                root_ui().push_skin(&current_skin.pause_button_skin);
+                g.draw_pause_button();
+                root_ui().pop_skin();
+            }
+            game::Screens::Editor => {
+                root_ui().push_skin(&current_skin.numbers_button_skin);
+                g.draw_number_buttons();
+                root_ui().pop_skin();
+                root_ui().push_skin(&current_skin.game_skin);
+                g.draw_game_screen(font, mouse_x, mouse_y, false);
+
+                let level_name = match g.get_editor_difficult() {
+                    game::Difficult::SuperEasy => "Начинающий",
+                    game::Difficult::Easy => "Легко",
+                    game::Difficult::Medium => "Средне",
+                    game::Difficult::Hard => "Сложно",
+                };
+                let status = format!("{}, {}", g.get_editor_solutions(), level_name);
+                let size = measure_text(&status, Some(font), (g.font_size / 1.5) as u16, 1.0);
+                draw_text_ex(&status, center_x - size.width / 2., g.start_y - 20., TextParams {
+                    font_size: (g.font_size / 1.5) as u16,
+                    font,
+                    color: Color::from_rgba(141, 141, 141, 255),
+                    ..Default::default()
+                });
+
+                root_ui().pop_skin();
+                root_ui().push_skin(&current_skin.big_button_skin);
+                let buttons_y = g.end_y + 10.;
+                if root_ui().button(vec2(g.start_x as f32, buttons_y), "Играть") {
+                    g.play_editor();
+                }
+                if root_ui().button(vec2(center_x + 60., buttons_y), "Экспорт") {
+                    g.export_editor();
+                }
+                match g.editor_export.as_mut() {
+                    None => {}
+                    Some(line) => {
+                        widgets::Editbox::new(hash!(), vec2((g.end_x - g.start_x) as f32, 30.))
+                            .position(vec2(g.start_x as f32, screen_height() - 50.))
+                            .ui(&mut *root_ui(), line);
+                    }
+                }
                 root_ui().pop_skin();
                 root_ui().push_skin(&current_skin.button_arrow_skin);
                 if root_ui().button(Vec2::new(g.start_x as f32, g.start_y - 50.), "J") {
                     g.current_screen = game::Screens::Start;
                 }
+                root_ui().pop_skin();
+            }
+            game::Screens::Replay => {
+                g.update_replay(get_frame_time() as f64);
+                root_ui().push_skin(&current_skin.game_skin);
+                g.draw_replay_screen(font, mouse_x, mouse_y);
+
+                let size = measure_text(&g.model.get_duration(), Some(font), g.font_size as u16, 1.0);
+                draw_text_ex(&g.model.get_duration(), center_x - size.width / 2., g.start_y - 20., TextParams {
+                    font_size: g.font_size as u16,
+                    font,
+                    color: Color::from_rgba(141, 141, 141, 255),
+                    ..Default::default()
+                });
+
+                root_ui().pop_skin();
+                root_ui().push_skin(&current_skin.big_button_skin);
+                let (play_name, speed_name) = match &g.replay {
+                    None => ("Играть".to_owned(), "x1".to_owned()),
+                    Some(replay) => {
+                        let play_name = match replay.is_playing {
+                            true => "Пауза".to_owned(),
+                            false => "Играть".to_owned(),
+                        };
+                        (play_name, format!("x{}", replay.speed()))
+                    }
+                };
+                let buttons_y = g.end_y + g.font_size * 3.;
+                if root_ui().button(vec2(g.start_x as f32, buttons_y), play_name) {
+                    match g.replay.as_mut() {
+                        None => {}
+                        Some(replay) => replay.play_or_stop(),
+                    }
+                }
+                if root_ui().button(vec2(center_x + 60., buttons_y), speed_name) {
+                    match g.replay.as_mut() {
+                        None => {}
+                        Some(replay) => replay.switch_speed(),
+                    }
+                }
+                root_ui().pop_skin();
+                root_ui().push_skin(&current_skin.button_arrow_skin);
+                if root_ui().button(Vec2::new(g.start_x as f32, g.start_y - 50.), "J") {
+                    g.current_screen = game::Screens::Start;
+                }
+                root_ui().pop_skin();
             }
         }
 
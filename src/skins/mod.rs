@@ -9,6 +9,7 @@ pub struct DefaultSkin {
     pub numbers_button_skin: Skin,
     pub big_button_skin: Skin,
     pub button_arrow_skin: Skin,
+    pub pause_button_skin: Skin,
     pub game_skin: Skin,
 }
 
@@ -73,6 +74,13 @@ impl DefaultSkin {
             .background_margin(RectOffset::new(0.0, 0.0, 0.0, 0.0))
             .build();
 
+        let pause_button_style = root_ui.style_builder()
+            .text_color(Color::from_rgba(141, 141, 141, 255))
+            .font_size(30)
+            .font(include_bytes!("../../assets/MontserratBold.ttf")).unwrap()
+            .margin(RectOffset::new(0.0, 0.0, 0.0, 0.0))
+            .background_margin(RectOffset::new(0.0, 0.0, 0.0, 0.0))
+            .build();
 
         let button_game_style = root_ui.style_builder()
             .text_color(Color::from_rgba(141, 141, 141, 255))
@@ -113,6 +121,10 @@ impl DefaultSkin {
                 button_style: button_arrow_style,
                 ..root_ui.default_skin()
             },
+            pause_button_skin: Skin {
+                button_style: pause_button_style,
+                ..root_ui.default_skin()
+            },
             game_skin: Skin {
                 window_style,
                 button_style: button_game_style,
@@ -0,0 +1,422 @@
+//! VK Mini App calls: sharing a win, progress in VK Storage and the friends
+//! leaderboard. Everything goes through `Bridge`, the page implements it with
+//! vk-bridge and `MockBridge` answers locally, so the same code runs off VK.
+
+use std::collections::HashMap;
+
+use crate::game::Difficult;
+use crate::json::{self, Json};
+
+// Version of the VK API for apps.getLeaderboard.
+const API_VERSION: &str = "5.131";
+
+/// Answer to the request `id`, Err keeps the error JSON of vk-bridge.
+pub struct Response {
+    pub id: u32,
+    pub result: Result<Json, String>,
+}
+
+pub trait Bridge {
+    /// Starts a vk-bridge method with JSON `params`, the answer comes
+    /// later from `poll` with the returned id.
+    fn send(&mut self, method: &str, params: &str) -> u32;
+    fn poll(&mut self) -> Vec<Response>;
+}
+
+/// One line of the friends leaderboard.
+#[derive(Clone, Debug)]
+pub struct Entry {
+    pub user_id: i64,
+    pub name: String,
+    pub score: i64,
+}
+
+enum Request {
+    Share,
+    SaveProgress,
+    LoadProgress,
+    SubmitScore,
+    Token,
+    Leaderboard,
+}
+
+pub struct Vk<B: Bridge> {
+    bridge: B,
+    pending: HashMap<u32, Request>,
+    token: Option<String>,
+    wants_leaderboard: bool,
+    // values read from VK Storage by key
+    pub progress: HashMap<String, String>,
+    // friends by score, best first, None until fetched
+    pub leaderboard: Option<Vec<Entry>>,
+    pub last_error: Option<String>,
+}
+
+impl<B: Bridge> Vk<B> {
+    pub fn new(bridge: B) -> Self {
+        return Vk {
+            bridge,
+            pending: HashMap::new(),
+            token: None,
+            wants_leaderboard: false,
+            progress: HashMap::new(),
+            leaderboard: None,
+            last_error: None,
+        };
+    }
+
+    pub fn bridge(&self) -> &B {
+        return &self.bridge;
+    }
+
+    fn send(&mut self, method: &str, params: String, request: Request) {
+        let id = self.bridge.send(method, &params);
+        self.pending.insert(id, request);
+    }
+
+    /// Opens the wall post box with the result of a won game.
+    pub fn share_to_wall(&mut self, difficult: Difficult, duration: &str, score: i64) {
+        let message = format!("Решил судоку ({}) за {} и набрал {} очков!", difficult_label(difficult), duration, score);
+        self.send("VKWebAppShowWallPostBox", format!("{{\"message\":{}}}", json::quote(&message)), Request::Share);
+    }
+
+    /// Opens the story editor with a link back to the app.
+    pub fn share_to_story(&mut self, app_id: i32) {
+        let params = format!(
+            "{{\"background_type\":\"none\",\"attachment\":{{\"text\":\"open\",\"type\":\"url\",\"url\":\"https://vk.com/app{}\"}}}}",
+            app_id,
+        );
+        self.send("VKWebAppShowStoryBox", params, Request::Share);
+    }
+
+    /// VK Storage keeps strings up to 4 KB per key and user.
+    pub fn save_progress(&mut self, key: &str, value: &str) {
+        self.progress.insert(key.to_string(), value.to_string());
+        let params = format!("{{\"key\":{},\"value\":{}}}", json::quote(key), json::quote(value));
+        self.send("VKWebAppStorageSet", params, Request::SaveProgress);
+    }
+
+    /// Reads `keys` into `progress`, keys without a value come back empty.
+    pub fn load_progress(&mut self, keys: &[&str]) {
+        let keys: Vec<String> = keys.iter().map(|k| json::quote(k)).collect();
+        self.send("VKWebAppStorageGet", format!("{{\"keys\":[{}]}}", keys.join(",")), Request::LoadProgress);
+    }
+
+    /// Records the score for the leaderboard, VK shows its own box for it.
+    pub fn submit_score(&mut self, score: i64) {
+        self.send("VKWebAppShowLeaderBoardBox", format!("{{\"user_result\":{}}}", score), Request::SubmitScore);
+    }
+
+    /// Friends' scores need a user token with the friends scope, the player
+    /// is asked for it the first time.
+    pub fn fetch_leaderboard(&mut self, app_id: i32) {
+        self.wants_leaderboard = true;
+        match self.token.clone() {
+            None => {
+                let params = format!("{{\"app_id\":{},\"scope\":\"friends\"}}", app_id);
+                self.send("VKWebAppGetAuthToken", params, Request::Token);
+            }
+            Some(token) => self.request_leaderboard(&token),
+        }
+    }
+
+    fn request_leaderboard(&mut self, token: &str) {
+        self.wants_leaderboard = false;
+        let params = format!(
+            "{{\"method\":\"apps.getLeaderboard\",\"params\":{{\"type\":\"score\",\"global\":0,\"extended\":1,\"v\":\"{}\",\"access_token\":{}}}}}",
+            API_VERSION,
+            json::quote(token),
+        );
+        self.send("VKWebAppCallAPIMethod", params, Request::Leaderboard);
+    }
+
+    /// Takes the answers that came since the previous frame.
+    pub fn update(&mut self) {
+        for response in self.bridge.poll() {
+            let request = match self.pending.remove(&response.id) {
+                None => { continue; }
+                Some(request) => request,
+            };
+            let data = match response.result {
+                Err(error) => {
+                    self.last_error = Some(error);
+                    continue;
+                }
+                Ok(data) => data,
+            };
+            match request {
+                Request::Share | Request::SaveProgress | Request::SubmitScore => {}
+                Request::LoadProgress => {
+                    for item in data.get("keys").and_then(|k| k.as_array()).unwrap_or(&vec![]) {
+                        if let (Some(key), Some(value)) = (item.get("key").and_then(|k| k.as_str()), item.get("value").and_then(|v| v.as_str())) {
+                            self.progress.insert(key.to_string(), value.to_string());
+                        }
+                    }
+                }
+                Request::Token => {
+                    self.token = data.get("access_token").and_then(|t| t.as_str()).map(|t| t.to_string());
+                    match self.token.clone() {
+                        None => { self.last_error = Some("no access_token".to_string()); }
+                        Some(token) => {
+                            if self.wants_leaderboard {
+                                self.request_leaderboard(&token);
+                            }
+                        }
+                    }
+                }
+                Request::Leaderboard => {
+                    self.leaderboard = parse_leaderboard(&data);
+                    if self.leaderboard.is_none() {
+                        self.last_error = Some(format!("bad leaderboard: {:?}", data));
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn difficult_label(difficult: Difficult) -> &'static str {
+    match difficult {
+        Difficult::SuperEasy => "начинающий",
+        Difficult::Easy => "легко",
+        Difficult::Medium => "средне",
+        Difficult::Hard => "сложно",
+    }
+}
+
+/// Entries from the answer of apps.getLeaderboard with `extended`.
+fn parse_leaderboard(data: &Json) -> Option<Vec<Entry>> {
+    let response = data.get("response")?;
+    let mut names: HashMap<i64, String> = HashMap::new();
+    for profile in response.get("profiles").and_then(|p| p.as_array()).unwrap_or(&vec![]) {
+        let id = match profile.get("id").and_then(|i| i.as_i64()) {
+            None => { continue; }
+            Some(id) => id,
+        };
+        let first = profile.get("first_name").and_then(|n| n.as_str()).unwrap_or("");
+        let last = profile.get("last_name").and_then(|n| n.as_str()).unwrap_or("");
+        names.insert(id, format!("{} {}", first, last).trim().to_string());
+    }
+    let mut entries = vec![];
+    for item in response.get("items")?.as_array()? {
+        let user_id = item.get("user_id")?.as_i64()?;
+        entries.push(Entry {
+            user_id,
+            name: names.get(&user_id).cloned().unwrap_or_else(|| format!("id{}", user_id)),
+            score: item.get("score")?.as_i64()?,
+        });
+    }
+    entries.sort_by_key(|e| std::cmp::Reverse(e.score));
+    return Some(entries);
+}
+
+/// vk-bridge on the page, see the vk_bridge plugin in static/script.js.
+#[cfg(target_arch = "wasm32")]
+pub struct PageBridge {
+    next_id: u32,
+}
+
+#[cfg(target_arch = "wasm32")]
+extern "C" {
+    fn vk_bridge_send(id: u32, method: *const u8, method_len: usize, params: *const u8, params_len: usize);
+}
+
+/// Version of the vk_bridge plugin the page must have, 0.1.0.
+#[cfg(target_arch = "wasm32")]
+#[no_mangle]
+pub extern "C" fn vk_bridge_crate_version() -> u32 {
+    return 1 << 16;
+}
+
+#[cfg(target_arch = "wasm32")]
+impl PageBridge {
+    pub fn new() -> Self {
+        return PageBridge { next_id: 1 };
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl Bridge for PageBridge {
+    fn send(&mut self, method: &str, params: &str) -> u32 {
+        let id = self.next_id;
+        self.next_id += 1;
+        unsafe {
+            vk_bridge_send(id, method.as_ptr(), method.len(), params.as_ptr(), params.len());
+        }
+        return id;
+    }
+
+    fn poll(&mut self) -> Vec<Response> {
+        return crate::host::take_bridge_responses()
+            .into_iter()
+            .map(|(id, ok, text)| {
+                let result = match (ok, json::parse(&text)) {
+                    (true, Some(data)) => Ok(data),
+                    _ => Err(text),
+                };
+                Response { id, result }
+            })
+            .collect();
+    }
+}
+
+/// Off VK the game talks to the mock.
+#[cfg(not(target_arch = "wasm32"))]
+pub type PageBridge = MockBridge;
+
+/// Answers like vk-bridge right away, keeps storage in memory and returns
+/// `friends` as the leaderboard. `sent` has every call for checks.
+pub struct MockBridge {
+    pub sent: Vec<(String, String)>,
+    pub storage: HashMap<String, String>,
+    pub friends: Vec<Entry>,
+    // methods that fail as if the player closed the box
+    pub failing: Vec<String>,
+    answers: Vec<Response>,
+}
+
+impl Default for MockBridge {
+    fn default() -> Self {
+        return MockBridge::new();
+    }
+}
+
+impl MockBridge {
+    pub fn new() -> Self {
+        return MockBridge { sent: vec![], storage: HashMap::new(), friends: vec![], failing: vec![], answers: vec![] };
+    }
+
+    fn answer(&mut self, method: &str, params: &Json) -> Result<String, String> {
+        if self.failing.iter().any(|m| m == method) {
+            return Err("{\"error_type\":\"client_error\",\"error_data\":{\"error_code\":4,\"error_reason\":\"User denied\"}}".to_string());
+        }
+        match method {
+            "VKWebAppShowWallPostBox" => Ok("{\"post_id\":1}".to_string()),
+            "VKWebAppShowStoryBox" | "VKWebAppShowLeaderBoardBox" => Ok("{\"result\":true}".to_string()),
+            "VKWebAppStorageSet" => {
+                let key = params.get("key").and_then(|k| k.as_str()).unwrap_or("");
+                let value = params.get("value").and_then(|v| v.as_str()).unwrap_or("");
+                self.storage.insert(key.to_string(), value.to_string());
+                Ok("{\"result\":true}".to_string())
+            }
+            "VKWebAppStorageGet" => {
+                let keys = params.get("keys").and_then(|k| k.as_array()).cloned().unwrap_or_default();
+                let items: Vec<String> = keys.iter()
+                    .filter_map(|k| k.as_str())
+                    .map(|k| format!("{{\"key\":{},\"value\":{}}}", json::quote(k), json::quote(self.storage.get(k).map(|v| v.as_str()).unwrap_or(""))))
+                    .collect();
+                Ok(format!("{{\"keys\":[{}]}}", items.join(",")))
+            }
+            "VKWebAppGetAuthToken" => Ok("{\"access_token\":\"mock\",\"scope\":\"friends\"}".to_string()),
+            "VKWebAppCallAPIMethod" => {
+                let items: Vec<String> = self.friends.iter()
+                    .map(|e| format!("{{\"user_id\":{},\"score\":{}}}", e.user_id, e.score))
+                    .collect();
+                let profiles: Vec<String> = self.friends.iter()
+                    .map(|e| format!("{{\"id\":{},\"first_name\":{},\"last_name\":\"\"}}", e.user_id, json::quote(&e.name)))
+                    .collect();
+                Ok(format!(
+                    "{{\"response\":{{\"count\":{},\"items\":[{}],\"profiles\":[{}]}}}}",
+                    items.len(),
+                    items.join(","),
+                    profiles.join(","),
+                ))
+            }
+            _ => Err(format!("{{\"error_type\":\"client_error\",\"error_data\":{{\"error_reason\":\"unknown method {}\"}}}}", method)),
+        }
+    }
+}
+
+impl Bridge for MockBridge {
+    fn send(&mut self, method: &str, params: &str) -> u32 {
+        self.sent.push((method.to_string(), params.to_string()));
+        let id = self.sent.len() as u32;
+        let result = match json::parse(params) {
+            None => Err(format!("bad params {}", params)),
+            Some(params) => self.answer(method, &params),
+        };
+        let result = match result {
+            Ok(text) => Ok(json::parse(&text).unwrap()),
+            Err(text) => Err(text),
+        };
+        self.answers.push(Response { id, result });
+        return id;
+    }
+
+    fn poll(&mut self) -> Vec<Response> {
+        return std::mem::take(&mut self.answers);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(user_id: i64, name: &str, score: i64) -> Entry {
+        return Entry { user_id, name: name.to_string(), score };
+    }
+
+    #[test]
+    fn progress_round_trip() {
+        let mut vk = Vk::new(MockBridge::new());
+        vk.save_progress("best_easy", "120");
+        vk.update();
+        assert_eq!(vk.bridge().storage.get("best_easy").map(|v| v.as_str()), Some("120"));
+
+        // a new session starts with nothing and reads the storage back
+        let mut bridge = MockBridge::new();
+        bridge.storage = vk.bridge().storage.clone();
+        let mut vk = Vk::new(bridge);
+        vk.load_progress(&["best_easy", "best_hard"]);
+        vk.update();
+        assert_eq!(vk.progress.get("best_easy").map(|v| v.as_str()), Some("120"));
+        assert_eq!(vk.progress.get("best_hard").map(|v| v.as_str()), Some(""));
+        assert!(vk.last_error.is_none());
+    }
+
+    #[test]
+    fn leaderboard_asks_for_a_token_once() {
+        let mut bridge = MockBridge::new();
+        bridge.friends = vec![entry(1, "Аня", 300), entry(2, "Борис", 900)];
+        let mut vk = Vk::new(bridge);
+        vk.fetch_leaderboard(7);
+        assert!(vk.leaderboard.is_none());
+        vk.update();
+        // the token came in this update, the leaderboard with it comes in the next one
+        vk.update();
+        let names: Vec<&str> = vk.leaderboard.as_ref().unwrap().iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["Борис", "Аня"]);
+
+        vk.fetch_leaderboard(7);
+        vk.update();
+        let methods: Vec<&str> = vk.bridge().sent.iter().map(|(m, _)| m.as_str()).collect();
+        assert_eq!(methods, vec!["VKWebAppGetAuthToken", "VKWebAppCallAPIMethod", "VKWebAppCallAPIMethod"]);
+        assert!(vk.bridge().sent[1].1.contains("\"access_token\":\"mock\""));
+    }
+
+    #[test]
+    fn failing_method_sets_last_error() {
+        let mut bridge = MockBridge::new();
+        bridge.failing = vec!["VKWebAppShowWallPostBox".to_string()];
+        let mut vk = Vk::new(bridge);
+        vk.share_to_story(7);
+        vk.update();
+        assert!(vk.last_error.is_none());
+        vk.share_to_wall(Difficult::Easy, "01:30", 200);
+        vk.update();
+        assert!(vk.last_error.as_ref().unwrap().contains("User denied"));
+    }
+
+    #[test]
+    fn denied_token_leaves_no_leaderboard() {
+        let mut bridge = MockBridge::new();
+        bridge.failing = vec!["VKWebAppGetAuthToken".to_string()];
+        let mut vk = Vk::new(bridge);
+        vk.fetch_leaderboard(7);
+        vk.update();
+        vk.update();
+        assert!(vk.leaderboard.is_none());
+        assert!(vk.last_error.is_some());
+        assert_eq!(vk.bridge().sent.len(), 1);
+    }
+}
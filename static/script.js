@@ -1345,6 +1345,48 @@ function miniquad_add_plugin(plugin) {
     plugins.push(plugin);
 }
 
+// Game events from the wasm side: started, won, lost, hint_used and abandoned.
+// Every event is dispatched on window as a "sudoku" event, the JSON is in `detail`.
+var sudoku_events_plugin = {
+    name: "sudoku_events",
+    version: "0.1.0",
+    register_plugin: function (importObject) {
+        importObject.env.sudoku_event = function (ptr, len) {
+            var detail = JSON.parse(UTF8ToString(ptr, len));
+            window.dispatchEvent(new CustomEvent("sudoku", { detail: detail }));
+        };
+    },
+};
+miniquad_add_plugin(sudoku_events_plugin);
+
+// vk-bridge calls from the wasm side, see src/vk.rs. The answer goes back
+// through the input buffer of the game with the id of the call.
+var vk_bridge_plugin = {
+    name: "vk_bridge",
+    version: "0.1.0",
+    register_plugin: function (importObject) {
+        importObject.env.vk_bridge_send = function (id, method_ptr, method_len, params_ptr, params_len) {
+            var method = UTF8ToString(method_ptr, method_len);
+            var params = JSON.parse(UTF8ToString(params_ptr, params_len));
+            var respond = function (ok, data) {
+                var bytes = new TextEncoder().encode(JSON.stringify(data));
+                var ptr = wasm_exports.input_buffer(bytes.length);
+                if (ptr === 0) {
+                    bytes = new TextEncoder().encode('{"error_type":"answer_too_long"}');
+                    ptr = wasm_exports.input_buffer(bytes.length);
+                    ok = 0;
+                }
+                new Uint8Array(wasm_memory.buffer, ptr, bytes.length).set(bytes);
+                wasm_exports.vk_bridge_response(id, ok, bytes.length);
+            };
+            vkBridge.send(method, params)
+                .then(data => respond(1, data))
+                .catch(error => respond(0, error));
+        };
+    },
+};
+miniquad_add_plugin(vk_bridge_plugin);
+
 // read module imports and create fake functions in import object
 // this is will allow to successfeully link wasm even with wrong version of gl.js
 // needed to workaround firefox bug with lost error on wasm linking errors